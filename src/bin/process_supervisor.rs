@@ -4,6 +4,7 @@ use process_supervisor::k8s::k8s_supervisor::{
     mark_itself_as_finished, remove_supervisor_finalizer, start_k8s_cycle,
 };
 use process_supervisor::server::http::http_server::start_http_server;
+use process_supervisor::shutdown::{shutdown_on_signal, EXIT_CODE_DRAINED, EXIT_CODE_INTERRUPTED};
 use process_supervisor::supervisor::{SlotsPopulationError, Supervisor};
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -19,29 +20,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     let supervisor_arc = Arc::new(RwLock::new(Supervisor::new(&env_params)));
 
-    //prepare the kill queue processing task
+    //prepare the kill queue processing task, it sends escalation signals at their deadlines
+    let supervisor = supervisor_arc.read().await.clone();
+    tokio::task::spawn(async move {
+        supervisor.run_kill_queue().await;
+    });
+
+    //prepare the watchdog task checking the running processes against their limits
     let sv_arc = Arc::clone(&supervisor_arc);
+    let watchdog_interval = Duration::from_secs(env_params.watchdog_interval_secs());
     tokio::task::spawn(async move {
         loop {
             let svg = sv_arc.read().await;
-            svg.process_kill_queue().await;
+            svg.run_watchdog().await;
             drop(svg);
-            tokio::time::sleep(Duration::from_secs(5)).await;
+            tokio::time::sleep(watchdog_interval).await;
         }
     });
 
-    //run the k8s cycle if we're within Kubernetes
+    //run the k8s cycle if we're within Kubernetes. It runs until the shutdown, so in its own task
     if k8s_params.is_some() {
         println!("Kubernetes parameters are available, proceeding with k8s cycle.");
-        start_k8s_cycle(
+        tokio::task::spawn(start_k8s_cycle(
             supervisor_arc.clone(),
             //send just a copy
             Arc::new(k8s_params.clone().unwrap()),
             // pod_name.clone(),
-        )
-        .await;
+        ));
     } else {
         println!("Running outside Kubernetes, skipping k8s cycle.");
+        //the pod finalizer does the same within Kubernetes
+        tokio::task::spawn(shutdown_on_signal(
+            supervisor_arc.clone(),
+            Duration::from_secs(env_params.shutdown_grace_period_secs()),
+        ));
     }
 
     //preparing a task to:
@@ -49,6 +61,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     //- clean finished processes
     //- run dispatcher processes if empty slots are available
     let sv_arc = Arc::clone(&supervisor_arc);
+    let child_exited = supervisor_arc.read().await.child_exit_notifier();
+    let mut terminate_mode = supervisor_arc.read().await.terminate_mode_receiver();
     let k8s_params_option_arc = Arc::new(k8s_params);
     tokio::task::spawn(async move {
         let mut is_drain_mode = false;
@@ -57,8 +71,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             //clean list from finished processes
             let working_processes_cnt = sv_g.process_states().await;
 
-            //perform only if pod name is available (we're in k8s)
-            if k8s_params_option_arc.is_some() {
+            //terminate mode: interrupt everything and leave without waiting for processes to finish
+            if sv_g.is_terminate_mode().await {
+                sv_g.terminate_all().await;
+                drop(sv_g);
+                if working_processes_cnt == 0 {
+                    //the terminate annotation is an explicit request, outside Kubernetes
+                    //terminate mode means the shutdown grace period was not enough
+                    let exit_code = match k8s_params_option_arc.as_ref() {
+                        Some(k8s_params) => {
+                            //remove finalizer from the pod so it can be deleted by Kubernetes
+                            remove_supervisor_finalizer(Arc::new(k8s_params)).await;
+                            EXIT_CODE_DRAINED
+                        }
+                        None => EXIT_CODE_INTERRUPTED,
+                    };
+                    println!("All processes are interrupted. Terminating supervisor...");
+                    std::process::exit(exit_code);
+                }
+            } else if k8s_params_option_arc.is_some() {
+                //perform only if pod name is available (we're in k8s)
                 //just copy the value from Arc
                 let k8s_params = k8s_params_option_arc.as_ref().clone().unwrap();
                 let k8s_params_arc = Arc::new(&k8s_params);
@@ -72,7 +104,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                         println!("Drain mode is caught. Will not populate anymore");
                         continue;
                     }
-                } else if working_processes_cnt == 0 {
+                } else {
+                    //processes obtained before the drain mode still have to be run
+                    sv_g.launch_deferred_processes().await;
+                    drop(sv_g);
+                }
+
+                if is_drain_mode && working_processes_cnt == 0 {
                     //terminate supervisor pod if is_drain_mode and there are no any working processes left
                     let res = mark_itself_as_finished(Arc::clone(&k8s_params_arc)).await;
                     if res.is_err() {
@@ -87,9 +125,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                     println!("Terminating supervisor pod...");
                     std::process::exit(0);
                 }
+            } else if sv_g.is_drain_mode().await {
+                //outside Kubernetes the drain mode comes from the shutdown signal
+                sv_g.launch_deferred_processes().await;
+                drop(sv_g);
+                if working_processes_cnt == 0 {
+                    println!("All processes are finished. Terminating supervisor...");
+                    std::process::exit(EXIT_CODE_DRAINED);
+                }
             }
 
-            tokio::time::sleep(Duration::from_secs(30)).await;
+            //interrupted processes which are not running (e.g. waiting for a retry) don't wake
+            //the loop up, so poll more often while terminating
+            let poll_interval = match *terminate_mode.borrow() {
+                true => Duration::from_secs(1),
+                false => Duration::from_secs(30),
+            };
+            //wake up as soon as any child exits to report it and refill its slot
+            tokio::select! {
+                _ = child_exited.notified() => {
+                    println!("A child process exited. Processing states...");
+                }
+                _ = terminate_mode.changed() => {
+                    println!("Terminate mode is caught. Interrupting processes...");
+                }
+                _ = tokio::time::sleep(poll_interval) => {}
+            }
         }
     });
 
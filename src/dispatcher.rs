@@ -25,18 +25,17 @@ pub enum DispatchState {
 const PROCESSING_MODE_REGULAR: isize = 1;
 const PROCESSING_MODE_SANDBOX: isize = 2;
 
-#[derive(PartialEq, Serialize, Deserialize, Debug)]
+#[derive(PartialEq, Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum ProcessingMode {
     Regular = PROCESSING_MODE_REGULAR,
     Sandbox = PROCESSING_MODE_SANDBOX,
 }
 
 impl ProcessingMode {
-    pub fn new(value: isize) -> ProcessingMode {
-        match value {
-            PROCESSING_MODE_REGULAR => ProcessingMode::Regular,
-            PROCESSING_MODE_SANDBOX => ProcessingMode::Sandbox,
-            _ => panic!("Unexpected ProcessingMode value"),
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessingMode::Regular => "regular",
+            ProcessingMode::Sandbox => "sandbox",
         }
     }
 }
@@ -51,6 +50,28 @@ pub struct AssignedProcess {
     #[serde(with = "chrono::serde::ts_milliseconds")]
     pub created_at: DateTime<Utc>,
     pub supervisor_id: String,
+    //overrides the worker profile deadline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime_secs: Option<u64>,
+    //job input delivered to the worker via stdin or a file, a JSON string is passed as is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+}
+
+impl AssignedProcess {
+    ///builds a process that was not obtained from the dispatcher (e.g. launched via HTTP API)
+    pub fn manual(id: String, supervisor_id: String) -> Self {
+        AssignedProcess {
+            id,
+            source_id: 0,
+            state: DispatchState::Processing,
+            r#mode: ProcessingMode::Regular,
+            created_at: Utc::now(),
+            supervisor_id,
+            max_runtime_secs: None,
+            payload: None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -87,6 +108,10 @@ impl DispatcherClient {
         }
     }
 
+    pub fn supervisor_id(&self) -> &str {
+        &self.supervisor_id
+    }
+
     pub async fn obtain_new_process(
         &self,
     ) -> Result<AssignedProcess, ProcessDispatcherClientError> {
@@ -157,10 +182,33 @@ impl DispatcherClient {
 pub struct ProcessFinishReport {
     process_id: String,
     result: String,
+    //set if the supervisor stopped the process itself, e.g. "memory limit exceeded"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    //the signal which terminated the process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signal: Option<i32>,
+    core_dumped: bool,
+    //how many times the process was launched, including local retries
+    attempts: u32,
 }
 
 impl ProcessFinishReport {
-    pub fn new(process_id: String, result: String) -> Self {
-        ProcessFinishReport { process_id, result }
+    pub fn new(
+        process_id: String,
+        result: String,
+        reason: Option<String>,
+        signal: Option<i32>,
+        core_dumped: bool,
+        attempts: u32,
+    ) -> Self {
+        ProcessFinishReport {
+            process_id,
+            result,
+            reason,
+            signal,
+            core_dumped,
+            attempts,
+        }
     }
 }
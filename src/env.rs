@@ -1,5 +1,20 @@
 use crate::dispatcher::{DEFAULT_OBTAIN_PROCESS_URL, DEFAULT_REPORT_PROCESS_FINISH_URL};
+use crate::supervisor::cgroup::CgroupLimits;
+use crate::supervisor::crash_loop::CrashLoopPolicy;
+use crate::supervisor::escalation::{parse_signal, EscalationChain};
+use crate::supervisor::process_payload::{PayloadDelivery, PayloadPolicy};
+use crate::supervisor::retry_policy::RetryPolicy;
+use crate::supervisor::workdir::WorkdirPolicy;
+use crate::supervisor::worker_profile::{
+    MemoryLimits, ResourceLimits, WorkerProfile, WorkerProfiles, DEFAULT_WORKER_ARGS,
+    DEFAULT_WORKER_PROGRAM,
+};
+use nix::sys::signal::Signal;
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
+
+const DEFAULT_SIGNAL_ALLOWLIST: &str = "SIGHUP,SIGUSR1,SIGUSR2";
 
 pub struct EnvParams {
     http_port: u16,
@@ -8,6 +23,19 @@ pub struct EnvParams {
     obtain_process_url: String,
     report_process_finish_url: String,
     supervisor_id: String,
+    worker_profiles: WorkerProfiles,
+    context_file_dir: Option<String>,
+    log_buffer_lines: usize,
+    log_retention_secs: u64,
+    forward_child_output: bool,
+    cgroup_parent: Option<String>,
+    watchdog_interval_secs: u64,
+    retry_policy: RetryPolicy,
+    crash_loop_policy: CrashLoopPolicy,
+    shutdown_grace_period_secs: u64,
+    signal_allowlist: Vec<Signal>,
+    payload_policy: PayloadPolicy,
+    workdir_policy: Option<WorkdirPolicy>,
 }
 
 impl EnvParams {
@@ -32,6 +60,58 @@ impl EnvParams {
     pub fn supervisor_id(&self) -> &str {
         &self.supervisor_id
     }
+
+    pub fn worker_profiles(&self) -> &WorkerProfiles {
+        &self.worker_profiles
+    }
+
+    pub fn context_file_dir(&self) -> Option<&str> {
+        self.context_file_dir.as_deref()
+    }
+
+    pub fn log_buffer_lines(&self) -> usize {
+        self.log_buffer_lines
+    }
+
+    pub fn log_retention_secs(&self) -> u64 {
+        self.log_retention_secs
+    }
+
+    pub fn forward_child_output(&self) -> bool {
+        self.forward_child_output
+    }
+
+    pub fn cgroup_parent(&self) -> Option<&str> {
+        self.cgroup_parent.as_deref()
+    }
+
+    pub fn watchdog_interval_secs(&self) -> u64 {
+        self.watchdog_interval_secs
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    pub fn crash_loop_policy(&self) -> &CrashLoopPolicy {
+        &self.crash_loop_policy
+    }
+
+    pub fn shutdown_grace_period_secs(&self) -> u64 {
+        self.shutdown_grace_period_secs
+    }
+
+    pub fn signal_allowlist(&self) -> &[Signal] {
+        &self.signal_allowlist
+    }
+
+    pub fn payload_policy(&self) -> &PayloadPolicy {
+        &self.payload_policy
+    }
+
+    pub fn workdir_policy(&self) -> Option<&WorkdirPolicy> {
+        self.workdir_policy.as_ref()
+    }
 }
 
 pub fn fetch_env_params() -> EnvParams {
@@ -79,6 +159,66 @@ pub fn fetch_env_params() -> EnvParams {
     let supervisor_id: String =
         env::var("HOST_NAME").expect("HOST_NAME is not set, please set it to supervisor id");
 
+    let worker_profiles = fetch_worker_profiles();
+
+    //if set, every worker gets its assigned process as a JSON file in this directory
+    let context_file_dir: Option<String> = env::var("WORKER_CONTEXT_FILE_DIR").ok();
+
+    let log_buffer_lines: usize = match env::var("LOG_BUFFER_LINES") {
+        Ok(lines) => lines.parse::<usize>().unwrap(),
+        Err(_) => {
+            println!("LOG_BUFFER_LINES is not set. Using default 1000");
+            1000
+        }
+    };
+
+    //how long the output of a finished process is available via HTTP API
+    let log_retention_secs: u64 = match env::var("LOG_RETENTION_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("LOG_RETENTION_SECS is not set. Using default 600");
+            600
+        }
+    };
+
+    //re-emit every child output line as JSON on the supervisor stdout
+    let forward_child_output: bool = match env::var("FORWARD_CHILD_OUTPUT") {
+        Ok(value) => value.parse::<bool>().unwrap(),
+        Err(_) => {
+            println!("FORWARD_CHILD_OUTPUT is not set. Using default true");
+            true
+        }
+    };
+
+    //cgroup v2 directory (e.g. /sys/fs/cgroup/workers) to create a cgroup per process in.
+    //Cgroups are not used if it is not set
+    let cgroup_parent: Option<String> = env::var("CGROUP_PARENT").ok();
+
+    //how often running processes are checked against their profile limits
+    let watchdog_interval_secs: u64 = match env::var("WATCHDOG_INTERVAL_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("WATCHDOG_INTERVAL_SECS is not set. Using default 1");
+            1
+        }
+    };
+
+    let retry_policy = fetch_retry_policy();
+    let crash_loop_policy = fetch_crash_loop_policy();
+
+    //outside Kubernetes: how long to wait for processes to finish after SIGTERM/SIGINT
+    let shutdown_grace_period_secs: u64 = match env::var("SHUTDOWN_GRACE_PERIOD_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("SHUTDOWN_GRACE_PERIOD_SECS is not set. Using default 30");
+            30
+        }
+    };
+
+    let signal_allowlist = fetch_signal_allowlist();
+    let payload_policy = fetch_payload_policy();
+    let workdir_policy = fetch_workdir_policy();
+
     EnvParams {
         http_port,
         sigterm_timeout_secs,
@@ -86,5 +226,372 @@ pub fn fetch_env_params() -> EnvParams {
         obtain_process_url,
         report_process_finish_url,
         supervisor_id,
+        worker_profiles,
+        context_file_dir,
+        log_buffer_lines,
+        log_retention_secs,
+        forward_child_output,
+        cgroup_parent,
+        watchdog_interval_secs,
+        retry_policy,
+        crash_loop_policy,
+        shutdown_grace_period_secs,
+        signal_allowlist,
+        payload_policy,
+        workdir_policy,
+    }
+}
+
+///reads CRASH_LOOP_MAX_FAILURES, CRASH_LOOP_WINDOW_SECS, CRASH_LOOP_MIN_RUNTIME_MS,
+///CRASH_LOOP_PAUSE_SECS and CRASH_LOOP_MAX_PAUSE_SECS variables
+fn fetch_crash_loop_policy() -> CrashLoopPolicy {
+    let max_failures: usize = match env::var("CRASH_LOOP_MAX_FAILURES") {
+        Ok(count) => count.parse::<usize>().unwrap(),
+        Err(_) => {
+            println!("CRASH_LOOP_MAX_FAILURES is not set. Using default 5");
+            5
+        }
+    };
+
+    let window_secs: u64 = match env::var("CRASH_LOOP_WINDOW_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("CRASH_LOOP_WINDOW_SECS is not set. Using default 60");
+            60
+        }
+    };
+
+    //failed processes exiting faster than this are counted as crashes
+    let min_runtime_ms: u64 = match env::var("CRASH_LOOP_MIN_RUNTIME_MS") {
+        Ok(ms) => ms.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("CRASH_LOOP_MIN_RUNTIME_MS is not set. Using default 1000");
+            1000
+        }
+    };
+
+    //doubled after every consecutive pause
+    let pause_secs: u64 = match env::var("CRASH_LOOP_PAUSE_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("CRASH_LOOP_PAUSE_SECS is not set. Using default 10");
+            10
+        }
+    };
+
+    let max_pause_secs: u64 = match env::var("CRASH_LOOP_MAX_PAUSE_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("CRASH_LOOP_MAX_PAUSE_SECS is not set. Using default 300");
+            300
+        }
+    };
+
+    CrashLoopPolicy {
+        max_failures,
+        window: Duration::from_secs(window_secs),
+        min_runtime: Duration::from_millis(min_runtime_ms),
+        initial_pause: Duration::from_secs(pause_secs),
+        max_pause: Duration::from_secs(max_pause_secs),
+    }
+}
+
+///reads WORKDIR_ROOT and WORKDIR_RETAIN_ON_FAILURE variables.
+///Processes share the working directory if the root is not set
+fn fetch_workdir_policy() -> Option<WorkdirPolicy> {
+    let root: String = match env::var("WORKDIR_ROOT") {
+        Ok(root) => root,
+        Err(_) => {
+            println!("WORKDIR_ROOT is not set. Per-process working directories are disabled");
+            return None;
+        }
+    };
+
+    let retain_on_failure: bool = match env::var("WORKDIR_RETAIN_ON_FAILURE") {
+        Ok(value) => value.parse::<bool>().unwrap(),
+        Err(_) => {
+            println!("WORKDIR_RETAIN_ON_FAILURE is not set. Using default false");
+            false
+        }
+    };
+
+    Some(WorkdirPolicy {
+        root,
+        retain_on_failure,
+    })
+}
+
+///reads PAYLOAD_DELIVERY, PAYLOAD_DIR and PAYLOAD_MAX_BYTES variables
+fn fetch_payload_policy() -> PayloadPolicy {
+    //"stdin" or "file"
+    let delivery: PayloadDelivery = match env::var("PAYLOAD_DELIVERY") {
+        Ok(delivery) => delivery.parse::<PayloadDelivery>().unwrap(),
+        Err(_) => {
+            println!("PAYLOAD_DELIVERY is not set. Using default file");
+            PayloadDelivery::File
+        }
+    };
+
+    let dir: String = match env::var("PAYLOAD_DIR") {
+        Ok(dir) => dir,
+        Err(_) => {
+            let dir = env::temp_dir()
+                .join("process_supervisor_payloads")
+                .to_string_lossy()
+                .into_owned();
+            println!("PAYLOAD_DIR is not set. Using default {}", dir);
+            dir
+        }
+    };
+
+    let max_bytes: usize = match env::var("PAYLOAD_MAX_BYTES") {
+        Ok(bytes) => bytes.parse::<usize>().unwrap(),
+        Err(_) => {
+            println!("PAYLOAD_MAX_BYTES is not set. Using default 1048576");
+            1048576
+        }
+    };
+
+    PayloadPolicy {
+        delivery,
+        dir,
+        max_bytes,
+    }
+}
+
+///reads SIGNAL_ALLOWLIST, the signals which may be sent to workers via the HTTP API.
+///Signals driving the process lifecycle are managed by the supervisor only
+fn fetch_signal_allowlist() -> Vec<Signal> {
+    //comma separated list of signal names, e.g. "SIGHUP,SIGUSR1"
+    let names: String = match env::var("SIGNAL_ALLOWLIST") {
+        Ok(names) => names,
+        Err(_) => {
+            println!(
+                "SIGNAL_ALLOWLIST is not set. Using default {}",
+                DEFAULT_SIGNAL_ALLOWLIST
+            );
+            DEFAULT_SIGNAL_ALLOWLIST.to_string()
+        }
+    };
+
+    names
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| {
+            let signal = parse_signal(name)
+                .unwrap_or_else(|e| panic!("Unable to parse SIGNAL_ALLOWLIST: {}", e));
+            if matches!(signal, Signal::SIGKILL | Signal::SIGSTOP | Signal::SIGCONT) {
+                panic!(
+                    "{} can't be in SIGNAL_ALLOWLIST, use the kill, pause and resume routes",
+                    signal
+                );
+            }
+            signal
+        })
+        .collect()
+}
+
+///reads RETRY_MAX_ATTEMPTS, RETRY_BACKOFF_SECS, RETRY_MAX_BACKOFF_SECS and RETRY_EXIT_CODES variables.
+///Failed runs are not retried by default
+fn fetch_retry_policy() -> RetryPolicy {
+    //including the first run
+    let max_attempts: u32 = match env::var("RETRY_MAX_ATTEMPTS") {
+        Ok(attempts) => attempts.parse::<u32>().unwrap(),
+        Err(_) => {
+            println!("RETRY_MAX_ATTEMPTS is not set. Using default 1");
+            1
+        }
+    };
+
+    //doubled after every retry
+    let backoff_secs: u64 = match env::var("RETRY_BACKOFF_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("RETRY_BACKOFF_SECS is not set. Using default 1");
+            1
+        }
+    };
+
+    let max_backoff_secs: u64 = match env::var("RETRY_MAX_BACKOFF_SECS") {
+        Ok(secs) => secs.parse::<u64>().unwrap(),
+        Err(_) => {
+            println!("RETRY_MAX_BACKOFF_SECS is not set. Using default 60");
+            60
+        }
+    };
+
+    //comma separated list of exit codes, e.g. "75,111"
+    let exit_codes: Vec<i32> = env::var("RETRY_EXIT_CODES")
+        .map(|codes| {
+            codes
+                .split(',')
+                .filter(|code| !code.trim().is_empty())
+                .map(|code| code.trim().parse::<i32>().unwrap())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    RetryPolicy::new(
+        max_attempts,
+        Duration::from_secs(backoff_secs),
+        Duration::from_secs(max_backoff_secs),
+        exit_codes,
+    )
+}
+
+///reads worker profiles from a JSON file set in WORKER_CONFIG_FILE (`{"regular": {..}, "sandbox": {..}}`)
+///or, if it is not set, from WORKER_* (regular mode) and SANDBOX_WORKER_* (sandbox mode) variables
+fn fetch_worker_profiles() -> WorkerProfiles {
+    if let Ok(path) = env::var("WORKER_CONFIG_FILE") {
+        println!("Reading worker profiles from {}", path);
+        let content = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Unable to read WORKER_CONFIG_FILE {}: {}", path, e));
+        let profiles: WorkerProfiles = serde_json::from_str(&content)
+            .unwrap_or_else(|e| panic!("Unable to parse WORKER_CONFIG_FILE {}: {}", path, e));
+        return profiles.with_sandbox_defaults();
+    }
+
+    let regular = fetch_worker_profile("WORKER_", None);
+    if env::var("SANDBOX_WORKER_PROGRAM").is_err() {
+        println!(
+            "WARNING: SANDBOX_WORKER_PROGRAM is not set. Sandbox jobs run the regular worker command"
+        );
+    }
+    let sandbox = fetch_worker_profile("SANDBOX_WORKER_", Some(&regular));
+    WorkerProfiles::new(regular, sandbox).with_sandbox_defaults()
+}
+
+///reads {prefix}PROGRAM, {prefix}ARGS, {prefix}WORKING_DIR, {prefix}ENV, {prefix}MAX_CHILDREN_COUNT,
+///{prefix}MAX_RUNTIME_SECS, {prefix}TERMINATION_CHAIN and {prefix}RLIMIT_*, {prefix}CGROUP_*, {prefix}MEMORY_* variables.
+///Unset command values are taken from the fallback profile
+fn fetch_worker_profile(prefix: &str, fallback: Option<&WorkerProfile>) -> WorkerProfile {
+    let var = |name: &str| env::var(format!("{}{}", prefix, name)).ok();
+
+    let program: String = match (var("PROGRAM"), fallback) {
+        (Some(program), _) => program,
+        (None, Some(fallback)) => fallback.program().to_string(),
+        (None, None) => {
+            println!(
+                "{}PROGRAM is not set. Using default {}",
+                prefix, DEFAULT_WORKER_PROGRAM
+            );
+            DEFAULT_WORKER_PROGRAM.to_string()
+        }
+    };
+
+    //whitespace separated list, e.g. "worker/worker.php --process={process_id}"
+    let args: Vec<String> = match (var("ARGS"), fallback) {
+        (Some(args), _) => args.split_whitespace().map(String::from).collect(),
+        (None, Some(fallback)) => fallback.args().to_vec(),
+        (None, None) => {
+            println!(
+                "{}ARGS is not set. Using default {}",
+                prefix, DEFAULT_WORKER_ARGS
+            );
+            DEFAULT_WORKER_ARGS
+                .split_whitespace()
+                .map(String::from)
+                .collect()
+        }
+    };
+
+    let working_dir: Option<String> =
+        var("WORKING_DIR").or_else(|| fallback.and_then(|f| f.working_dir().map(String::from)));
+
+    //comma separated list of KEY=VALUE pairs, e.g. "APP_ENV=prod,SOURCE={source_id}"
+    let worker_env: HashMap<String, String> = match (var("ENV"), fallback) {
+        (Some(value), _) => parse_key_value_list(&value),
+        (None, Some(fallback)) => fallback.env().clone(),
+        (None, None) => HashMap::new(),
+    };
+
+    //concurrency and resource limits are never inherited from the fallback profile
+    let max_children_count: Option<usize> =
+        var("MAX_CHILDREN_COUNT").map(|count| count.parse::<usize>().unwrap());
+
+    let resource_limits = ResourceLimits {
+        max_memory_bytes: var("RLIMIT_AS_BYTES").map(|limit| limit.parse::<u64>().unwrap()),
+        max_cpu_secs: var("RLIMIT_CPU_SECS").map(|limit| limit.parse::<u64>().unwrap()),
+        max_open_files: var("RLIMIT_NOFILE").map(|limit| limit.parse::<u64>().unwrap()),
+    };
+
+    let cgroup_limits = CgroupLimits {
+        memory_max: var("CGROUP_MEMORY_MAX"),
+        cpu_max: var("CGROUP_CPU_MAX"),
+        pids_max: var("CGROUP_PIDS_MAX"),
+    };
+
+    let memory_limits = MemoryLimits {
+        soft_limit_kb: var("MEMORY_SOFT_LIMIT_KB").map(|limit| limit.parse::<u64>().unwrap()),
+        hard_limit_kb: var("MEMORY_HARD_LIMIT_KB").map(|limit| limit.parse::<u64>().unwrap()),
+    };
+
+    let max_runtime_secs: Option<u64> =
+        var("MAX_RUNTIME_SECS").map(|secs| secs.parse::<u64>().unwrap());
+
+    //ordered SIGNAL:wait_secs steps, e.g. "SIGINT:5,SIGTERM:20,SIGKILL"
+    let termination_chain: Option<EscalationChain> = match var("TERMINATION_CHAIN") {
+        Some(chain) => Some(chain.parse::<EscalationChain>().unwrap_or_else(|e| {
+            panic!(
+                "Unable to parse {}TERMINATION_CHAIN {}: {}",
+                prefix, chain, e
+            )
+        })),
+        None => fallback.and_then(|f| f.termination_chain().cloned()),
+    };
+
+    WorkerProfile::new(
+        program,
+        args,
+        working_dir,
+        worker_env,
+        max_children_count,
+        resource_limits,
+        cgroup_limits,
+        memory_limits,
+        max_runtime_secs,
+        termination_chain,
+    )
+}
+
+fn parse_key_value_list(value: &str) -> HashMap<String, String> {
+    value
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (key.trim().to_string(), value.to_string()),
+            None => panic!("Unable to parse KEY=VALUE pair: {}", pair),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_value_lists() {
+        let parsed = parse_key_value_list(" APP_ENV=prod,SOURCE={source_id},EMPTY=, ");
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed["APP_ENV"], "prod");
+        assert_eq!(parsed["SOURCE"], "{source_id}");
+        assert_eq!(parsed["EMPTY"], "");
+    }
+
+    #[test]
+    fn keeps_equal_signs_in_values() {
+        let parsed = parse_key_value_list("QUERY=a=b");
+        assert_eq!(parsed["QUERY"], "a=b");
+    }
+
+    #[test]
+    fn parses_an_empty_list() {
+        assert!(parse_key_value_list("").is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unable to parse KEY=VALUE pair")]
+    fn rejects_pairs_without_a_value() {
+        parse_key_value_list("APP_ENV");
     }
 }
@@ -98,10 +98,7 @@ pub fn get_current_pod_name() -> Result<String, std::io::Error> {
         Ok(pn) => Ok(pn),
         Err(_) => {
             println!("Unable to get {} env variable value", ENV_HOSTNAME);
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Unable to get pod name",
-            ))
+            Err(std::io::Error::other("Unable to get pod name"))
         }
     }
 }
@@ -113,10 +110,7 @@ async fn get_namespace() -> Result<String, std::io::Error> {
         Ok(ns) => Ok(ns.trim().to_string()),
         Err(_) => {
             println!("Unable to get namespace");
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Unable to get namespace",
-            ))
+            Err(std::io::Error::other("Unable to get namespace"))
         }
     }
 }
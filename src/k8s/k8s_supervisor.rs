@@ -14,7 +14,6 @@ use tokio::sync::RwLock;
 
 struct ReconcileContext {
     pods: Arc<Api<Pod>>,
-    k8s_params: Arc<K8sParams>,
     supervisor: Arc<RwLock<Supervisor>>,
 }
 
@@ -194,7 +193,6 @@ async fn start_controller(
         error_policy,
         Arc::new(ReconcileContext {
             pods: Arc::new(pod_api),
-            k8s_params: k8s_params.clone(),
             supervisor: Arc::clone(&supervisor),
         }),
     )
@@ -215,7 +213,7 @@ async fn start_controller(
     .await;
 }
 
-async fn add_drain_pod_annotation(ctx: &Arc<ReconcileContext>, name: &String) -> kube::Result<Pod> {
+async fn add_drain_pod_annotation(ctx: &Arc<ReconcileContext>, name: &str) -> kube::Result<Pod> {
     let patch = json!({
         "metadata": {
             "annotations": {
@@ -225,7 +223,7 @@ async fn add_drain_pod_annotation(ctx: &Arc<ReconcileContext>, name: &String) ->
     });
     ctx.pods
         .patch(
-            &name,
+            name,
             &PatchParams::apply("process-supervisor/drain"),
             &Patch::Merge(&patch),
         )
@@ -2,4 +2,5 @@ mod dispatcher;
 pub mod env;
 pub mod k8s;
 pub mod server;
+pub mod shutdown;
 pub mod supervisor;
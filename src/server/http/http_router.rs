@@ -1,15 +1,21 @@
 use async_trait::async_trait;
 use bytes::Bytes;
 use core::str;
-use http_body_util::Full;
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Full};
 use hyper::http::Error;
 use hyper::Response;
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::fmt::Debug;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::supervisor::Supervisor;
+
+///response body of any route, either a full one or a stream of chunks
+pub type ResponseBody = UnsyncBoxBody<Bytes, Infallible>;
+
 #[async_trait]
 // pub trait Handleable: Send + Sync + Debug + Clone {
 pub trait Handleable: Send + Sync + Debug {
@@ -30,18 +36,30 @@ pub trait Handleable: Send + Sync + Debug {
         _route_req_params: HashMap<String, String>,
         _body: String,
         _supervisor_arc: Arc<RwLock<Supervisor>>,
-    ) -> Result<Response<Full<Bytes>>, Error>;
+    ) -> Result<Response<ResponseBody>, Error>;
 
     fn prepare_response(
         &self,
         message: String,
         http_status_code: u16,
-    ) -> Result<Response<Full<Bytes>>, Error> {
+    ) -> Result<Response<ResponseBody>, Error> {
         let bytes = bytes::Bytes::from(message);
         let body = Full::new(bytes);
         Response::builder()
             .status(http_status_code)
-            .body(body.to_owned())
+            .body(body.boxed_unsync())
+    }
+
+    fn prepare_stream_response(
+        &self,
+        body: ResponseBody,
+        content_type: &str,
+    ) -> Result<Response<ResponseBody>, Error> {
+        Response::builder()
+            .status(200)
+            .header("Content-Type", content_type)
+            .header("Cache-Control", "no-cache")
+            .body(body)
     }
 }
 
@@ -64,10 +82,7 @@ pub struct RouteData {
     pub params: Option<HashMap<String, ParamType>>,
 }
 
-pub fn route_request_params(
-    req_path: String,
-    route: &Box<dyn Handleable>,
-) -> HashMap<String, String> {
+pub fn route_request_params(req_path: String, route: &dyn Handleable) -> HashMap<String, String> {
     let route_params = match route.params() {
         Some(params) => params,
         None => return HashMap::new(),
@@ -1,12 +1,17 @@
-use super::http_router::{Handleable, RouteData};
+use super::http_router::{Handleable, ResponseBody, RouteData};
+use crate::supervisor::process_selector::{ProcessSelector, SelectedProcessResult};
 use crate::supervisor::Supervisor;
 use async_trait::async_trait;
 use bytes::Bytes;
-use http_body_util::Full;
+use futures_util::stream::{self, StreamExt};
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::Frame;
 use hyper::http::Error;
 use hyper::Response;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
 use tokio::time::Instant;
 
@@ -29,16 +34,44 @@ impl Handleable for LaunchRoute {
     async fn handle_data(
         &self,
         route_req_params: HashMap<String, String>,
-        _body: String,
+        body: String,
         supervisor_arc: Arc<RwLock<Supervisor>>,
-    ) -> Result<Response<Full<Bytes>>, Error> {
+    ) -> Result<Response<ResponseBody>, Error> {
         let id = route_req_params.get("id").unwrap().parse::<String>()?;
 
         let supervisor_guard = supervisor_arc.read().await;
-        let future = supervisor_guard.launch(id.clone());
+        //the supervisor is shutting down, new processes would only delay it
+        if supervisor_guard.is_drain_mode().await {
+            let message = format!(
+                "Unable to start a process for source {}: the supervisor is draining",
+                id
+            );
+            return self.prepare_response(message, 503);
+        }
+        if supervisor_guard.is_pause_mode().await {
+            let message = format!(
+                "Unable to start a process for source {}: the supervisor is paused",
+                id
+            );
+            return self.prepare_response(message, 503);
+        }
+        let mut assigned_process = supervisor_guard.manual_process(id.clone());
+        //an optional JSON body is the payload for the worker
+        if !body.trim().is_empty() {
+            match serde_json::from_str(&body) {
+                Ok(payload) => assigned_process.payload = Some(payload),
+                Err(e) => {
+                    let message = format!("Invalid payload for source {}: {}", id, e);
+                    return self.prepare_response(message, 400);
+                }
+            }
+        }
+        let future = supervisor_guard.launch(assigned_process);
         let result = future.await;
         let http_status_code = match result.is_success() {
             true => 200,
+            false if result.is_conflict() => 409,
+            false if result.is_rejected() => 413,
             false => 500,
         };
         let message = match result.is_success() {
@@ -76,7 +109,7 @@ impl Handleable for GetStateList {
         _route_req_params: HashMap<String, String>,
         _body: String,
         supervisor_arc: Arc<RwLock<Supervisor>>,
-    ) -> Result<Response<Full<Bytes>>, Error> {
+    ) -> Result<Response<ResponseBody>, Error> {
         let before_time = Instant::now();
         println!(
             "GetStateList: before supervisor_clone: {:?}",
@@ -105,6 +138,130 @@ impl Handleable for GetStateList {
     }
 }
 
+//"status" route
+#[derive(Debug, Clone)]
+pub struct GetStatus {
+    pub data: RouteData,
+}
+
+#[async_trait]
+impl Handleable for GetStatus {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        _route_req_params: HashMap<String, String>,
+        _body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let supervisor_guard = supervisor_arc.read().await;
+        let status = supervisor_guard.status().await;
+        drop(supervisor_guard);
+
+        let json_message = serde_json::to_string(&status).unwrap();
+        self.prepare_response(json_message, 200)
+    }
+}
+
+//"logs" route
+#[derive(Debug, Clone)]
+pub struct GetLogs {
+    pub data: RouteData,
+}
+
+#[async_trait]
+impl Handleable for GetLogs {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        route_req_params: HashMap<String, String>,
+        _body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let id = match route_req_params.get("id") {
+            Some(id) => id.clone(),
+            None => return self.prepare_response("Missing process id".to_owned(), 400),
+        };
+        let supervisor_guard = supervisor_arc.read().await;
+        let logs = supervisor_guard.get_logs(&id).await;
+        drop(supervisor_guard);
+
+        match logs {
+            Some(lines) => {
+                let json_message = serde_json::to_string(&lines).unwrap();
+                self.prepare_response(json_message, 200)
+            }
+            None => self.prepare_response(format!("No logs found for process {}", id), 404),
+        }
+    }
+}
+
+//"follow logs" route, streams the process output as Server-Sent Events until the process
+//output is closed
+#[derive(Debug, Clone)]
+pub struct FollowLogs {
+    pub data: RouteData,
+}
+
+#[async_trait]
+impl Handleable for FollowLogs {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        route_req_params: HashMap<String, String>,
+        _body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let id = match route_req_params.get("id") {
+            Some(id) => id.clone(),
+            None => return self.prepare_response("Missing process id".to_owned(), 400),
+        };
+        let supervisor_guard = supervisor_arc.read().await;
+        let follow = supervisor_guard.follow_logs(&id).await;
+        drop(supervisor_guard);
+
+        let (lines, receiver) = match follow {
+            Some(follow) => follow,
+            None => return self.prepare_response(format!("No logs found for process {}", id), 404),
+        };
+
+        let buffered = stream::iter(lines).map(|line| Ok(sse_event(&line)));
+        let live = stream::unfold(receiver, |receiver| async move {
+            let mut receiver = receiver?;
+            let frame = match receiver.recv().await {
+                Ok(line) => sse_event(&line),
+                Err(RecvError::Lagged(skipped)) => {
+                    Frame::data(Bytes::from(format!(": {} lines skipped\n\n", skipped)))
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            Some((Ok(frame), Some(receiver)))
+        });
+        let body = StreamBody::new(buffered.chain(live)).boxed_unsync();
+
+        self.prepare_stream_response(body, "text/event-stream")
+    }
+}
+
+fn sse_event<T: Serialize>(data: &T) -> Frame<Bytes> {
+    let json = serde_json::to_string(data).unwrap();
+    Frame::data(Bytes::from(format!("data: {}\n\n", json)))
+}
+
 //"404" route
 #[derive(Debug, Clone)]
 pub struct Route404 {
@@ -119,13 +276,13 @@ impl Handleable for Route404 {
     fn clone_box(&self) -> Box<dyn Handleable> {
         Box::new(self.clone())
     }
-    // async fn handle_data(&self, body: String) -> Result<Response<Full<Bytes>>, Error> {
+    // async fn handle_data(&self, body: String) -> Result<Response<ResponseBody>, Error> {
     async fn handle_data(
         &self,
         _route_req_params: HashMap<String, String>,
         _body: String,
         _supervisor_arc: Arc<RwLock<Supervisor>>,
-    ) -> Result<Response<Full<Bytes>>, Error> {
+    ) -> Result<Response<ResponseBody>, Error> {
         self.prepare_response("404".to_owned(), 404)
     }
 }
@@ -149,7 +306,7 @@ impl Handleable for TerminateRoute {
         route_req_params: HashMap<String, String>,
         _body: String,
         supervisor_arc: Arc<RwLock<Supervisor>>,
-    ) -> Result<Response<Full<Bytes>>, Error> {
+    ) -> Result<Response<ResponseBody>, Error> {
         let id = route_req_params.get("id").unwrap().parse::<String>()?;
         let supervisor_guard = supervisor_arc.read().await;
         let result = supervisor_guard.terminate(id.clone()).await;
@@ -173,6 +330,262 @@ impl Handleable for TerminateRoute {
     }
 }
 
+//bulk "terminate" route, processes are selected by the JSON body, e.g. {"source_id": 12}
+#[derive(Debug, Clone)]
+pub struct BulkTerminateRoute {
+    pub data: RouteData,
+}
+
+#[async_trait]
+impl Handleable for BulkTerminateRoute {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        _route_req_params: HashMap<String, String>,
+        body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let selector = match ProcessSelector::parse(&body) {
+            Ok(selector) => selector,
+            Err(e) => return self.prepare_response(e, 400),
+        };
+        let supervisor_guard = supervisor_arc.read().await;
+        let results = supervisor_guard.terminate_selected(&selector).await;
+        drop(supervisor_guard);
+
+        let json_message = serde_json::to_string(&BulkResponse::new(results)).unwrap();
+        self.prepare_response(json_message, 200)
+    }
+}
+
+//bulk "kill" route, responds once every selected process exits
+#[derive(Debug, Clone)]
+pub struct BulkKillRoute {
+    pub data: RouteData,
+}
+
+#[async_trait]
+impl Handleable for BulkKillRoute {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        _route_req_params: HashMap<String, String>,
+        body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let selector = match ProcessSelector::parse(&body) {
+            Ok(selector) => selector,
+            Err(e) => return self.prepare_response(e, 400),
+        };
+        //do not hold the supervisor lock while waiting for the processes to exit
+        let supervisor = supervisor_arc.read().await.clone();
+        let results = supervisor.kill_selected(&selector).await;
+
+        let json_message = serde_json::to_string(&BulkResponse::new(results)).unwrap();
+        self.prepare_response(json_message, 200)
+    }
+}
+
+//"pause" and "resume" routes
+#[derive(Debug, Clone)]
+pub struct PauseRoute {
+    pub data: RouteData,
+    pub is_resume: bool,
+}
+
+#[async_trait]
+impl Handleable for PauseRoute {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        route_req_params: HashMap<String, String>,
+        _body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let id = match route_req_params.get("id") {
+            Some(id) => id.clone(),
+            None => return self.prepare_response("Missing process id".to_owned(), 400),
+        };
+        let supervisor_guard = supervisor_arc.read().await;
+        let (result, action) = match self.is_resume {
+            true => (supervisor_guard.resume(id.clone()).await, "resumed"),
+            false => (supervisor_guard.pause(id.clone()).await, "paused"),
+        };
+
+        let http_status_code = match result.is_success() {
+            true => 200,
+            false => 500,
+        };
+        let message = match result.is_success() {
+            true => format!("A process {} was {}", id, action),
+            false => format!(
+                "Failed to get a process {} {}. Error: {:?}",
+                id,
+                action,
+                result
+                    .error_message()
+                    .unwrap_or(&"Unknown error".to_owned())
+            ),
+        };
+
+        self.prepare_response(message, http_status_code)
+    }
+}
+
+//supervisor-wide "pause" and "resume" routes
+#[derive(Debug, Clone)]
+pub struct PauseAllRoute {
+    pub data: RouteData,
+    pub is_resume: bool,
+}
+
+#[async_trait]
+impl Handleable for PauseAllRoute {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        _route_req_params: HashMap<String, String>,
+        _body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let supervisor_guard = supervisor_arc.read().await;
+        let results = match self.is_resume {
+            true => supervisor_guard.resume_all().await,
+            false => supervisor_guard.pause_all().await,
+        };
+        drop(supervisor_guard);
+
+        let json_message = serde_json::to_string(&BulkResponse::new(results)).unwrap();
+        self.prepare_response(json_message, 200)
+    }
+}
+
+//"signal" route, only signals from SIGNAL_ALLOWLIST are sent
+#[derive(Debug, Clone)]
+pub struct SignalRoute {
+    pub data: RouteData,
+}
+
+#[async_trait]
+impl Handleable for SignalRoute {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        route_req_params: HashMap<String, String>,
+        _body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let (id, signal_name) = match (route_req_params.get("id"), route_req_params.get("signal")) {
+            (Some(id), Some(signal_name)) => (id.clone(), signal_name),
+            _ => return self.prepare_response("Missing process id or signal".to_owned(), 400),
+        };
+        let supervisor_guard = supervisor_arc.read().await;
+        let signal = match supervisor_guard.allowed_signal(signal_name) {
+            Ok(signal) => signal,
+            Err(e) => return self.prepare_response(e, 400),
+        };
+        let result = supervisor_guard.send_signal(id.clone(), signal).await;
+
+        let http_status_code = match result.is_success() {
+            true => 200,
+            false => 500,
+        };
+        let message = match result.is_success() {
+            true => format!("A process {} got {}", id, signal),
+            false => format!(
+                "Failed to send {} to a process {}. Error: {:?}",
+                signal,
+                id,
+                result
+                    .error_message()
+                    .unwrap_or(&"Unknown error".to_owned())
+            ),
+        };
+
+        self.prepare_response(message, http_status_code)
+    }
+}
+
+//broadcast "signal" route, the signal is sent to every process
+#[derive(Debug, Clone)]
+pub struct SignalAllRoute {
+    pub data: RouteData,
+}
+
+#[async_trait]
+impl Handleable for SignalAllRoute {
+    fn data(&self) -> RouteData {
+        self.data.clone()
+    }
+    fn clone_box(&self) -> Box<dyn Handleable> {
+        Box::new(self.clone())
+    }
+    async fn handle_data(
+        &self,
+        route_req_params: HashMap<String, String>,
+        _body: String,
+        supervisor_arc: Arc<RwLock<Supervisor>>,
+    ) -> Result<Response<ResponseBody>, Error> {
+        let signal_name = match route_req_params.get("signal") {
+            Some(signal_name) => signal_name,
+            None => return self.prepare_response("Missing signal".to_owned(), 400),
+        };
+        let supervisor_guard = supervisor_arc.read().await;
+        let signal = match supervisor_guard.allowed_signal(signal_name) {
+            Ok(signal) => signal,
+            Err(e) => return self.prepare_response(e, 400),
+        };
+        let results = supervisor_guard.send_signal_to_all(signal).await;
+        drop(supervisor_guard);
+
+        let json_message = serde_json::to_string(&BulkResponse::new(results)).unwrap();
+        self.prepare_response(json_message, 200)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BulkResponse {
+    succeeded: usize,
+    failed: usize,
+    results: Vec<SelectedProcessResult>,
+}
+
+impl BulkResponse {
+    fn new(results: Vec<SelectedProcessResult>) -> Self {
+        let succeeded = results.iter().filter(|result| result.is_success).count();
+        Self {
+            succeeded,
+            failed: results.len() - succeeded,
+            results,
+        }
+    }
+}
+
 //"kill" route
 #[derive(Debug, Clone)]
 pub struct KillRoute {
@@ -192,10 +605,11 @@ impl Handleable for KillRoute {
         route_req_params: HashMap<String, String>,
         _body: String,
         supervisor_arc: Arc<RwLock<Supervisor>>,
-    ) -> Result<Response<Full<Bytes>>, Error> {
+    ) -> Result<Response<ResponseBody>, Error> {
         let id = route_req_params.get("id").unwrap().parse::<String>()?;
-        let supervisor_guard = supervisor_arc.read().await;
-        let result = supervisor_guard.kill_old(id.clone()).await;
+        //do not hold the supervisor lock while waiting for the process to exit
+        let supervisor = supervisor_arc.read().await.clone();
+        let result = supervisor.kill(id.clone()).await;
 
         let http_status_code = match result.is_success() {
             true => 200,
@@ -1,9 +1,14 @@
-use super::http_router::{route, route_request_params, Handleable, ParamType, RouteData};
-use super::http_routes::{GetStateList, KillRoute, LaunchRoute, Route404, TerminateRoute};
+use super::http_router::{
+    route, route_request_params, Handleable, ParamType, ResponseBody, RouteData,
+};
+use super::http_routes::{
+    BulkKillRoute, BulkTerminateRoute, FollowLogs, GetLogs, GetStateList, GetStatus, KillRoute,
+    LaunchRoute, PauseAllRoute, PauseRoute, Route404, SignalAllRoute, SignalRoute, TerminateRoute,
+};
 use crate::supervisor::Supervisor;
 use http_body_util::BodyExt;
 use http_body_util::Full;
-use hyper::body::{Bytes, Incoming};
+use hyper::body::Incoming;
 use hyper::server::conn::http1;
 use hyper::service::Service;
 use hyper::{Request, Response};
@@ -70,7 +75,7 @@ struct HttpService {
 }
 
 impl Service<Request<Incoming>> for HttpService {
-    type Response = Response<Full<Bytes>>;
+    type Response = Response<ResponseBody>;
 
     type Error = Infallible;
 
@@ -80,6 +85,7 @@ impl Service<Request<Incoming>> for HttpService {
         // let router = init_router();
         let routes = self.routes.clone();
         let supervisor = self.supervisor_arc.clone();
+        let default_route = self.default_route.clone();
         Box::pin(async move {
             println!(
                 "Request: {} {:?} Body: {:?}",
@@ -93,13 +99,17 @@ impl Service<Request<Incoming>> for HttpService {
                 request.uri().path().to_owned(),
                 routes,
             );
-            let route = route_feature.await.unwrap_or(default_route());
-            let route_req_params = route_request_params(request.uri().path().to_owned(), &route);
+            let route = route_feature.await.unwrap_or(default_route);
+            let route_req_params =
+                route_request_params(request.uri().path().to_owned(), route.as_ref());
             let body = request.collect().await;
 
             if let Err(err) = body {
                 let err_body = Full::new(bytes::Bytes::from(err.to_string()));
-                let response = Response::builder().status(500).body(err_body).unwrap();
+                let response = Response::builder()
+                    .status(500)
+                    .body(err_body.boxed_unsync())
+                    .unwrap();
                 return Ok(response);
             }
 
@@ -113,10 +123,13 @@ impl Service<Request<Incoming>> for HttpService {
 
             let response = response_future.await.unwrap_or_else(|err| {
                 let err_body = Full::new(bytes::Bytes::from(err.to_string()));
-                Response::builder().status(500).body(err_body).unwrap()
+                Response::builder()
+                    .status(500)
+                    .body(err_body.boxed_unsync())
+                    .unwrap()
             });
 
-            println!("Response: {} {:?}", response.status(), response.body());
+            println!("Response: {}", response.status());
             Ok(response)
         })
     }
@@ -146,6 +159,69 @@ fn init_routes() -> Vec<Box<dyn Handleable>> {
                 params: Some(HashMap::from([("id".to_owned(), ParamType::Integer)])),
             },
         }),
+        Box::new(BulkTerminateRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/terminate".to_owned(),
+                params: None,
+            },
+        }),
+        Box::new(BulkKillRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/kill".to_owned(),
+                params: None,
+            },
+        }),
+        Box::new(PauseRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/pause/{id}".to_owned(),
+                params: Some(HashMap::from([("id".to_owned(), ParamType::AnyString)])),
+            },
+            is_resume: false,
+        }),
+        Box::new(PauseRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/resume/{id}".to_owned(),
+                params: Some(HashMap::from([("id".to_owned(), ParamType::AnyString)])),
+            },
+            is_resume: true,
+        }),
+        Box::new(PauseAllRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/pause".to_owned(),
+                params: None,
+            },
+            is_resume: false,
+        }),
+        Box::new(PauseAllRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/resume".to_owned(),
+                params: None,
+            },
+            is_resume: true,
+        }),
+        Box::new(SignalRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/signal/{id}/{signal}".to_owned(),
+                params: Some(HashMap::from([
+                    ("id".to_owned(), ParamType::AnyString),
+                    ("signal".to_owned(), ParamType::AnyString),
+                ])),
+            },
+        }),
+        Box::new(SignalAllRoute {
+            data: RouteData {
+                method: "POST".to_owned(),
+                path: "/signal/{signal}".to_owned(),
+                params: Some(HashMap::from([("signal".to_owned(), ParamType::AnyString)])),
+            },
+        }),
         Box::new(GetStateList {
             data: RouteData {
                 method: "GET".to_owned(),
@@ -153,6 +229,27 @@ fn init_routes() -> Vec<Box<dyn Handleable>> {
                 params: None,
             },
         }),
+        Box::new(GetStatus {
+            data: RouteData {
+                method: "GET".to_owned(),
+                path: "/status".to_owned(),
+                params: None,
+            },
+        }),
+        Box::new(GetLogs {
+            data: RouteData {
+                method: "GET".to_owned(),
+                path: "/logs/{id}".to_owned(),
+                params: Some(HashMap::from([("id".to_owned(), ParamType::AnyString)])),
+            },
+        }),
+        Box::new(FollowLogs {
+            data: RouteData {
+                method: "GET".to_owned(),
+                path: "/logs/{id}/follow".to_owned(),
+                params: Some(HashMap::from([("id".to_owned(), ParamType::AnyString)])),
+            },
+        }),
     ]
 }
 
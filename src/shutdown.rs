@@ -0,0 +1,57 @@
+use crate::supervisor::Supervisor;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::RwLock;
+
+///all processes finished by themselves within the grace period
+pub const EXIT_CODE_DRAINED: i32 = 0;
+///some processes had to be interrupted
+pub const EXIT_CODE_INTERRUPTED: i32 = 1;
+///finish reports could not be delivered in time after the interruption
+pub const EXIT_CODE_REPORTS_LOST: i32 = 2;
+
+//how long to wait for the interrupted processes to be reported after SIGKILL would have been sent
+const REPORTS_FLUSH_TIMEOUT: Duration = Duration::from_secs(15);
+
+///graceful shutdown outside Kubernetes: on SIGTERM/SIGINT the supervisor switches to drain mode,
+///when the grace period is over (or on the second signal) it switches to terminate mode.
+///The main loop exits once every process is reported
+pub async fn shutdown_on_signal(supervisor_arc: Arc<RwLock<Supervisor>>, grace_period: Duration) {
+    wait_for_signal().await;
+    println!(
+        "Shutdown signal is caught. Draining, grace period: {:?}",
+        grace_period
+    );
+    let sv_g = supervisor_arc.read().await;
+    sv_g.set_is_drain_mode().await;
+    //wake the main loop up so it exits right away if nothing is running
+    sv_g.child_exit_notifier().notify_one();
+    drop(sv_g);
+
+    tokio::select! {
+        _ = tokio::time::sleep(grace_period) => {
+            println!("Grace period is over. Interrupting the rest of processes...");
+        }
+        _ = wait_for_signal() => {
+            println!("Second shutdown signal is caught. Interrupting processes...");
+        }
+    }
+    let sv_g = supervisor_arc.read().await;
+    sv_g.set_is_terminate_mode().await;
+    let escalation_duration = sv_g.max_escalation_duration();
+    drop(sv_g);
+
+    tokio::time::sleep(escalation_duration + REPORTS_FLUSH_TIMEOUT).await;
+    println!("Unable to report all processes in time. Exiting...");
+    std::process::exit(EXIT_CODE_REPORTS_LOST);
+}
+
+async fn wait_for_signal() {
+    let mut sigterm = signal(SignalKind::terminate()).expect("Unable to listen to SIGTERM");
+    let mut sigint = signal(SignalKind::interrupt()).expect("Unable to listen to SIGINT");
+    tokio::select! {
+        _ = sigterm.recv() => println!("SIGTERM is received"),
+        _ = sigint.recv() => println!("SIGINT is received"),
+    }
+}
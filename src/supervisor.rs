@@ -1,33 +1,104 @@
 use crate::dispatcher;
-use crate::dispatcher::DispatcherClient;
+use crate::dispatcher::{AssignedProcess, DispatcherClient, ProcessingMode};
 use crate::env::EnvParams;
-use nix::sys::signal::{self};
-use nix::unistd::Pid;
+use cgroup::{Cgroup, CgroupUsage};
+use crash_loop::{CrashLoopDetector, CrashLoopStatus};
+use escalation::{parse_signal, EscalationChain};
+use futures_util::future::join_all;
+use kill_queue::{KillQueue, ScheduledStep};
+use lifecycle::{LifecycleState, Transition};
+use nix::sys::signal::{self, Signal};
+use process_events::{ProcessEvent, ProcessEventKind};
+use process_logs::{LogBuffer, LogBuffers, LogLine, OutputOrigin, OutputStream};
+use process_metrics::ProcessMetrics;
+use process_payload::{PayloadDelivery, PayloadPolicy};
+use process_selector::{ProcessSelector, SelectedProcessResult};
 #[cfg(target_os = "linux")]
 use procfs::process::Process;
 use results::TerminateResult;
-use results::{KillResult, LaunchResult, OldKillResult};
+use results::{KillResult, LaunchResult, PauseResult, SignalResult};
+use retry_policy::RetryPolicy;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::io::Error;
-use std::process::{Child, Command};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{ExitStatus, Stdio};
 use std::sync::Arc;
-use std::time::SystemTime;
-use tokio::sync::RwLock;
+use supervised_process::{Processes, SupervisedProcess, TerminationReason};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, Command};
+use tokio::sync::broadcast;
+use tokio::sync::{watch, Notify, RwLock};
 use tokio::task;
 use tokio::time::{sleep, sleep_until, Duration, Instant};
-
+use workdir::WorkdirPolicy;
+use worker_profile::{WorkerProfile, WorkerProfiles};
+
+pub mod cgroup;
+pub mod crash_loop;
+pub mod escalation;
+mod kill_queue;
+mod lifecycle;
+mod process_context;
+mod process_events;
+mod process_group;
+mod process_id;
+mod process_logs;
+mod process_metrics;
+pub mod process_payload;
+pub mod process_selector;
 mod results;
+pub mod retry_policy;
+mod supervised_process;
+pub mod workdir;
+pub mod worker_profile;
+
+//how long to wait for the child exit after SIGKILL was sent
+const SIGKILL_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Debug, Serialize)]
 pub struct ChildState {
     id: String,
+    pid: u32,
+    //1 for the first run, increased on every local retry
+    attempt: u32,
+    state: LifecycleState,
+    transitions: Vec<Transition>,
+    //time spent stopped by pauses, it does not count towards the deadline
+    paused_secs: u64,
     is_running: bool,
     is_finished: bool,
     exit_code: Option<i32>,
+    //the signal which terminated the process, there is no exit code in this case
+    signal: Option<i32>,
+    core_dumped: bool,
     is_killed: bool,
     rss_anon_memory_kb: Option<u64>,
+    //available only while the process is running
+    metrics: Option<ProcessMetrics>,
+    //subprocesses of the worker which are still alive after the worker itself exited
+    alive_descendant_pids: Vec<u32>,
+    //set only if the process runs in its own cgroup
+    cgroup_usage: Option<CgroupUsage>,
+    //set if the supervisor decided to stop the process
+    termination_reason: Option<TerminationReason>,
+    events: Vec<ProcessEvent>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SupervisorStatus {
+    is_drain_mode: bool,
+    is_terminate_mode: bool,
+    //running processes are stopped and no new ones are launched
+    is_pause_mode: bool,
+    processes_count: usize,
+    deferred_processes_count: usize,
+    pending_retries_count: usize,
+    //escalation steps waiting for their deadlines
+    pending_kill_steps_count: usize,
+    //slots population is paused while the worker keeps crashing
+    crash_loop: CrashLoopStatus,
 }
 
 impl ChildState {
@@ -38,59 +109,384 @@ impl ChildState {
 
 impl fmt::Display for ChildState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "is_running: {}, is_finished: {}, exit_code: {:?}, is_killed: {}, rss_anon_memory_kb: {:?}",
-               self.is_running, self.is_finished, self.exit_code, self.is_killed, self.rss_anon_memory_kb)
+        write!(f, "pid: {}, attempt: {}, state: {}, is_running: {}, is_finished: {}, exit_code: {:?}, signal: {:?}, core_dumped: {}, is_killed: {}, rss_anon_memory_kb: {:?}, metrics: {:?}, alive_descendant_pids: {:?}, cgroup_usage: {:?}, termination_reason: {:?}",
+               self.pid, self.attempt, self.state.as_str(), self.is_running, self.is_finished, self.exit_code, self.signal, self.core_dumped, self.is_killed, self.rss_anon_memory_kb, self.metrics, self.alive_descendant_pids, self.cgroup_usage, self.termination_reason)
     }
 }
 
 #[derive(Debug)]
 pub struct Supervisor {
     dispatcher_client: DispatcherClient,
-    processes: Arc<RwLock<HashMap<String, Child>>>,
-    kill_queue: Arc<RwLock<HashMap<String, u64>>>,
+    processes: Processes,
+    //obtained processes waiting for a free slot of their processing mode
+    deferred_processes: Arc<RwLock<VecDeque<AssignedProcess>>>,
+    //failed processes waiting for a relaunch, with their next attempt number
+    pending_retries: Arc<RwLock<HashMap<String, u32>>>,
+    //ids being launched right now, they are not in the processes list yet
+    launching_ids: Arc<RwLock<HashSet<String>>>,
+    kill_queue: Arc<RwLock<KillQueue>>,
+    //woken up when a step is scheduled, so an earlier deadline is not missed
+    kill_queue_changed: Arc<Notify>,
     is_drain_mode: Arc<RwLock<bool>>,
-    is_terminate_mode: Arc<RwLock<bool>>,
+    //a watch channel, so waiting tasks (e.g. retries) can be interrupted
+    is_terminate_mode: Arc<watch::Sender<bool>>,
+    is_pause_mode: Arc<watch::Sender<bool>>,
+    signal_allowlist: Vec<Signal>,
+    payload_policy: PayloadPolicy,
+    workdir_policy: Option<WorkdirPolicy>,
     max_children_count: usize,
     sig_term_timeout: u64,
+    worker_profiles: WorkerProfiles,
+    context_file_dir: Option<String>,
+    //captured stdout/stderr per process id, kept for a while after the process is gone
+    log_buffers: LogBuffers,
+    log_buffer_lines: usize,
+    log_retention_secs: u64,
+    forward_child_output: bool,
+    //notified every time a child exits
+    child_exited: Arc<Notify>,
+    cgroup_parent: Option<String>,
+    retry_policy: RetryPolicy,
+    crash_loop: Arc<RwLock<CrashLoopDetector>>,
 }
 
 impl Supervisor {
     pub fn new(env_params: &EnvParams) -> Self {
+        if let Some(parent) = env_params.cgroup_parent() {
+            if let Err(e) = cgroup::prepare_parent(parent) {
+                println!("Unable to enable controllers in cgroup {}: {}", parent, e);
+            }
+        }
         Self {
             dispatcher_client: DispatcherClient::new(env_params),
             processes: Arc::new(RwLock::new(HashMap::new())),
-            kill_queue: Arc::new(RwLock::new(HashMap::new())),
+            deferred_processes: Arc::new(RwLock::new(VecDeque::new())),
+            pending_retries: Arc::new(RwLock::new(HashMap::new())),
+            launching_ids: Arc::new(RwLock::new(HashSet::new())),
+            kill_queue: Arc::new(RwLock::new(KillQueue::new())),
+            kill_queue_changed: Arc::new(Notify::new()),
             is_drain_mode: Arc::new(RwLock::new(false)),
-            is_terminate_mode: Arc::new(RwLock::new(false)),
+            is_terminate_mode: Arc::new(watch::Sender::new(false)),
+            is_pause_mode: Arc::new(watch::Sender::new(false)),
+            signal_allowlist: env_params.signal_allowlist().to_vec(),
+            payload_policy: env_params.payload_policy().clone(),
+            workdir_policy: env_params.workdir_policy().cloned(),
             max_children_count: env_params.max_children_count(),
             sig_term_timeout: env_params.sigterm_timeout_secs(),
+            worker_profiles: env_params.worker_profiles().clone(),
+            context_file_dir: env_params.context_file_dir().map(String::from),
+            log_buffers: Arc::new(RwLock::new(HashMap::new())),
+            log_buffer_lines: env_params.log_buffer_lines(),
+            log_retention_secs: env_params.log_retention_secs(),
+            forward_child_output: env_params.forward_child_output(),
+            child_exited: Arc::new(Notify::new()),
+            cgroup_parent: env_params.cgroup_parent().map(String::from),
+            retry_policy: env_params.retry_policy().clone(),
+            crash_loop: Arc::new(RwLock::new(CrashLoopDetector::new(
+                env_params.crash_loop_policy().clone(),
+            ))),
+        }
+    }
+
+    ///builds a process description for a launch which is not coming from the dispatcher
+    pub fn manual_process(&self, id: String) -> AssignedProcess {
+        AssignedProcess::manual(id, self.dispatcher_client.supervisor_id().to_string())
+    }
+
+    pub async fn launch(&self, assigned_process: AssignedProcess) -> LaunchResult {
+        self.launch_attempt(assigned_process, 1).await
+    }
+
+    async fn launch_attempt(
+        &self,
+        assigned_process: AssignedProcess,
+        attempt: u32,
+    ) -> LaunchResult {
+        let id = assigned_process.id.clone();
+        //a bad id is the dispatcher problem, not a sign of a broken worker
+        if let Err(e) = process_id::validate(&id) {
+            let mut result = LaunchResult::new();
+            result.set_error(format!("Invalid process id: {}", e));
+            return result;
+        }
+        //the second launch would overwrite the supervised process and share its files
+        if !self.reserve_id(&id, attempt).await {
+            let mut result = LaunchResult::new();
+            result.set_conflict(format!("Process {} is already supervised", id));
+            return result;
+        }
+        let result = self.spawn_process(assigned_process, attempt).await;
+        //a launched process is in the processes list already
+        self.launching_ids.write().await.remove(&id);
+        //the worker is fine, the job is not
+        if result.is_rejected() {
+            println!("Process {} is rejected: {:?}", id, result.error_message());
+            self.report_not_running(&id, Some(TerminationReason::PayloadRejected), attempt)
+                .await;
+            return result;
+        }
+        if let Some(error_message) = result.error_message() {
+            self.crash_loop.write().await.record_failure(format!(
+                "process {} failed to launch: {}",
+                id, error_message
+            ));
         }
+        result
+    }
+
+    //marks the id as being launched unless it is supervised already.
+    //A retry relaunches the id it is pending for
+    async fn reserve_id(&self, id: &str, attempt: u32) -> bool {
+        let processes_guard = self.processes.read().await;
+        let pending_retries_guard = self.pending_retries.read().await;
+        let mut launching_ids_guard = self.launching_ids.write().await;
+        let is_retry = attempt > 1;
+        if processes_guard.contains_key(id) || (!is_retry && pending_retries_guard.contains_key(id))
+        {
+            return false;
+        }
+        launching_ids_guard.insert(id.to_string())
     }
 
-    pub async fn launch(&self, id: String) -> LaunchResult {
-        let mut command = Command::new("php");
-        command.arg("worker/worker.php");
+    async fn spawn_process(&self, assigned_process: AssignedProcess, attempt: u32) -> LaunchResult {
+        let profile = self.worker_profiles.for_mode(assigned_process.mode);
+        let mut std_command = profile.command(&assigned_process);
+        //a new process group, so the whole worker tree can be signaled at once
+        std_command.process_group(0);
+        let id = assigned_process.id.clone();
 
         let mut result = LaunchResult::new();
 
+        //checked first, so nothing is created for a rejected process
+        let payload_bytes = match self.payload_bytes(&assigned_process) {
+            Ok(payload_bytes) => payload_bytes,
+            Err(e) => {
+                result.set_rejected(e);
+                return result;
+            }
+        };
+
+        let cgroup = match self.attach_cgroup(&id, profile, &mut std_command).await {
+            Ok(cgroup) => cgroup,
+            Err(e) => {
+                result.set_error(e);
+                return result;
+            }
+        };
+
+        let mut command = Command::from(std_command);
+        command.envs(process_context::context_env(&assigned_process));
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        if let Some(dir) = &self.context_file_dir {
+            match process_context::write_context_file(dir, &assigned_process).await {
+                Ok(path) => {
+                    command.env(process_context::ENV_CONTEXT_FILE, path);
+                }
+                Err(e) => {
+                    if let Some(cgroup) = cgroup {
+                        cgroup.remove().await;
+                    }
+                    result.set_error(format!("Failed to write context file: {}", e));
+                    return result;
+                }
+            }
+        }
+
+        if let Err(e) = self.prepare_workdir(&id, profile, &mut command).await {
+            self.remove_launch_files(&id).await;
+            if let Some(cgroup) = cgroup {
+                cgroup.remove().await;
+            }
+            result.set_error(e);
+            return result;
+        }
+
+        let stdin_payload = match self.prepare_payload(&id, payload_bytes, &mut command).await {
+            Ok(stdin_payload) => stdin_payload,
+            Err(e) => {
+                self.remove_launch_files(&id).await;
+                self.remove_workdir(&id).await;
+                if let Some(cgroup) = cgroup {
+                    cgroup.remove().await;
+                }
+                result.set_error(e);
+                return result;
+            }
+        };
+
         let spawn_result = command.spawn();
         match spawn_result {
-            Ok(child) => {
-                let pid = child.id();
-                let processes_arc = self.processes.clone();
-                processes_arc.write().await.insert(id, child);
-                drop(processes_arc);
+            Ok(mut child) => {
+                //the PID is always available until the child is awaited
+                let pid = child.id().unwrap_or_default();
+                self.capture_output(&assigned_process, &mut child).await;
+                if let Some(bytes) = stdin_payload {
+                    write_stdin_payload(&id, &mut child, bytes);
+                }
+                let (exit_status_sender, exit_status) = watch::channel(None);
+                let mut process =
+                    SupervisedProcess::new(pid, assigned_process, exit_status, cgroup, attempt);
+                //the process must be listed before the exit watcher could mark it as exited
+                if let Err(e) = process.transition(LifecycleState::Running) {
+                    println!("Process {}: {}", id, e);
+                }
+                self.processes.write().await.insert(id.clone(), process);
+                supervised_process::watch_exit(
+                    id,
+                    child,
+                    exit_status_sender,
+                    self.processes.clone(),
+                    self.child_exited.clone(),
+                );
                 result.set_success(pid);
                 result
             }
             Err(e) => {
                 // println!("Failed to start command");
+                self.remove_launch_files(&id).await;
+                self.remove_workdir(&id).await;
+                if let Some(cgroup) = cgroup {
+                    cgroup.remove().await;
+                }
                 result.set_error(e.to_string());
                 result
             }
         }
     }
 
+    //creates a fresh scratch directory for the process if the working directory root is set
+    async fn prepare_workdir(
+        &self,
+        id: &str,
+        profile: &WorkerProfile,
+        command: &mut Command,
+    ) -> Result<(), String> {
+        let policy = match &self.workdir_policy {
+            Some(policy) => policy,
+            None => return Ok(()),
+        };
+        //launches of supervised ids are rejected before, but the directory of a running
+        //process must never be wiped
+        if self.processes.read().await.contains_key(id) {
+            return Err(format!("Working directory of process {} is in use", id));
+        }
+        let path = workdir::create_workdir(&policy.root, id)
+            .await
+            .map_err(|e| format!("Failed to create working directory: {}", e))?;
+        //an explicit profile working directory wins, the scratch one is still available via env
+        if profile.working_dir().is_none() {
+            command.current_dir(&path);
+        }
+        command.env(workdir::ENV_WORKDIR, path);
+        Ok(())
+    }
+
+    //removes the scratch directory unless the run failed and failed runs are retained
+    async fn release_workdir(&self, id: &str, is_success: bool) {
+        let policy = match &self.workdir_policy {
+            Some(policy) => policy,
+            None => return,
+        };
+        if !is_success && policy.retain_on_failure {
+            println!(
+                "Keeping working directory {:?} of failed process {}",
+                workdir::workdir_path(&policy.root, id).unwrap_or_default(),
+                id
+            );
+            return;
+        }
+        workdir::remove_workdir(&policy.root, id).await;
+    }
+
+    async fn remove_workdir(&self, id: &str) {
+        if let Some(policy) = &self.workdir_policy {
+            workdir::remove_workdir(&policy.root, id).await;
+        }
+    }
+
+    //returns the payload as it is delivered to the worker, unless it is too big
+    fn payload_bytes(&self, assigned_process: &AssignedProcess) -> Result<Option<Vec<u8>>, String> {
+        let payload = match &assigned_process.payload {
+            Some(payload) => payload,
+            None => return Ok(None),
+        };
+        let bytes = process_payload::payload_bytes(payload);
+        if bytes.len() > self.payload_policy.max_bytes {
+            return Err(format!(
+                "Payload of {} bytes exceeds PAYLOAD_MAX_BYTES {}",
+                bytes.len(),
+                self.payload_policy.max_bytes
+            ));
+        }
+        Ok(Some(bytes))
+    }
+
+    //sets the command up for the payload delivery. Returns the bytes to be written
+    //to the child stdin once it is spawned
+    async fn prepare_payload(
+        &self,
+        id: &str,
+        bytes: Option<Vec<u8>>,
+        command: &mut Command,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let bytes = match bytes {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        match self.payload_policy.delivery {
+            PayloadDelivery::Stdin => {
+                command.stdin(Stdio::piped());
+                Ok(Some(bytes))
+            }
+            PayloadDelivery::File => {
+                let path =
+                    process_payload::write_payload_file(&self.payload_policy.dir, id, &bytes)
+                        .await
+                        .map_err(|e| format!("Failed to write payload file: {}", e))?;
+                command.env(process_payload::ENV_PAYLOAD_FILE, path);
+                Ok(None)
+            }
+        }
+    }
+
+    //creates a cgroup for the process (if cgroups are configured) and makes the child join it
+    //before exec, so the limits apply to everything the worker forks
+    async fn attach_cgroup(
+        &self,
+        id: &str,
+        profile: &WorkerProfile,
+        command: &mut std::process::Command,
+    ) -> Result<Option<Cgroup>, String> {
+        let parent = match &self.cgroup_parent {
+            Some(parent) => parent,
+            None => return Ok(None),
+        };
+        let cgroup = Cgroup::create(parent, id, profile.cgroup_limits())
+            .map_err(|e| format!("Failed to create cgroup for process {}: {}", id, e))?;
+        let procs = match cgroup.open_procs() {
+            Ok(procs) => procs,
+            Err(e) => {
+                cgroup.remove().await;
+                return Err(format!(
+                    "Failed to open cgroup.procs for process {}: {}",
+                    id, e
+                ));
+            }
+        };
+        //SAFETY: the closure only calls write(2), which is async-signal-safe
+        unsafe {
+            command.pre_exec(move || {
+                //"0" moves the writing process itself
+                nix::unistd::write(&procs, b"0")?;
+                Ok(())
+            });
+        }
+        Ok(Some(cgroup))
+    }
+
+    ///sends the first signal of the escalation chain to the process group of a running process,
+    ///the kill queue follows the rest of the chain
     pub async fn terminate(&self, id: String) -> TerminateResult {
         let before_time = Instant::now();
 
@@ -98,7 +494,7 @@ impl Supervisor {
         let mut processes_guard = processes_arc.write().await;
 
         //extract child PID from the processes
-        let child = processes_guard.get_mut(&id);
+        let process = processes_guard.get_mut(&id);
 
         println!(
             "terminate: After getting of child from the process list time: {:?}",
@@ -106,276 +502,561 @@ impl Supervisor {
         );
 
         let mut result = TerminateResult::new();
-        if child.is_none() {
+        if process.is_none() {
             result.set_error("Child not found PID for SIGTERM sending".to_owned());
             return result;
         }
-        let child = child.unwrap();
-        let pid: i32 = child.id() as i32;
-
-        drop(processes_guard);
+        let process = process.unwrap();
+        if process.is_finished() {
+            //the PID could be reused already
+            result.set_error(format!("Process {} is already finished", id));
+            return result;
+        }
+        let chain = self.escalation_chain(process.mode());
+        let first_signal = chain.first().signal;
+        let is_paused = process.state() == LifecycleState::Paused;
+        //a chain of a single SIGKILL step skips the graceful part
+        let state = match first_signal {
+            Signal::SIGKILL => LifecycleState::Killing,
+            _ => LifecycleState::Terminating,
+        };
+        if let Err(e) = process.lifecycle().check_transition(state) {
+            result.set_error(format!("Process {} can't be terminated: {}", id, e));
+            return result;
+        }
+        let pid = process.pid;
 
+        //the state changes only once the signal is delivered, the lock keeps the check valid
         println!(
-            "terminate: After dropping time: {:?}",
+            "terminate: Sending {} to PID: {}, escalation: {}",
+            first_signal, pid, chain
+        );
+        if let Err(e) = process_group::signal_group(pid, first_signal) {
+            result.set_error(e.to_string());
+            return result;
+        }
+        println!(
+            "terminate: After {} sending: {:?}",
+            first_signal,
             Instant::now().duration_since(before_time)
         );
+        if let Err(e) = process.transition(state) {
+            println!("Process {}: {}", id, e);
+        }
+        //a stopped process handles the signal only once it is continued, SIGKILL needs no help
+        let mut signal_result = Ok(());
+        if is_paused && first_signal != Signal::SIGKILL {
+            println!("terminate: Sending SIGCONT to paused PID: {}", pid);
+            signal_result = process_group::signal_group(pid, Signal::SIGCONT);
+        }
+
+        drop(processes_guard);
 
-        println!("terminate: Sending SIGTERM to PID: {}", pid);
-        let signal_result = signal::kill(Pid::from_raw(pid), signal::SIGTERM);
         println!(
-            "terminate: After SIGTERM sending: {:?}",
+            "terminate: After dropping time: {:?}",
             Instant::now().duration_since(before_time)
         );
 
         match signal_result {
-            Ok(_) => {
-                let start = SystemTime::now();
-                self.kill_queue.clone().write().await.insert(
+            Ok(_) => result.set_success(),
+            Err(e) => result.set_error(e.to_string()),
+        }
+        //the signal is delivered already, the chain goes on even if the process was not continued
+        self.schedule_next_step(&id, pid, &chain, 0).await;
+        result
+    }
+
+    ///terminate mode: interrupts every running or paused process following its escalation chain.
+    ///Obtained but not launched processes are reported as interrupted right away.
+    ///Processes launched meanwhile are caught by the next call
+    pub async fn terminate_all(&self) {
+        let ids: Vec<String> = self
+            .processes
+            .read()
+            .await
+            .iter()
+            .filter(|(_, process)| {
+                matches!(
+                    process.state(),
+                    LifecycleState::Running | LifecycleState::Paused
+                )
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in ids {
+            let result = self.terminate(id.clone()).await;
+            if !result.is_success() {
+                println!(
+                    "Failed to interrupt process {}: {:?}",
                     id,
-                    start
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs(),
+                    result.error_message()
                 );
-                result.set_success();
-                result
+                continue;
             }
-            Err(e) => {
-                result.set_error(e.to_string());
-                result
+            if let Some(process) = self.processes.write().await.get_mut(&id) {
+                if process.termination_reason().is_none() {
+                    process.set_termination_reason(TerminationReason::Interrupted);
+                }
             }
         }
+
+        let deferred: Vec<AssignedProcess> =
+            self.deferred_processes.write().await.drain(..).collect();
+        for assigned_process in deferred {
+            println!(
+                "Deferred process {} is interrupted before its launch",
+                assigned_process.id
+            );
+            self.report_not_running(
+                &assigned_process.id,
+                Some(TerminationReason::Interrupted),
+                0,
+            )
+            .await;
+        }
     }
 
-    pub async fn kill_old(&self, id: String) -> OldKillResult {
+    //reports a process which is not running anymore and is not in the processes list
+    async fn report_not_running(&self, id: &str, reason: Option<TerminationReason>, attempts: u32) {
+        let report = dispatcher::ProcessFinishReport::new(
+            id.to_string(),
+            dispatcher::REPORT_STATUS_ERROR.to_string(),
+            reason.map(|reason| reason.as_str().to_string()),
+            None,
+            false,
+            attempts,
+        );
+        if let Err(e) = self.dispatcher_client.report_process_finish(report).await {
+            println!("Failed to report process finish: {:?}", e);
+        }
+    }
+
+    ///starts the escalation chain of the process (unless terminate() did it already) and waits
+    ///for its exit
+    pub async fn kill(&self, id: String) -> KillResult {
         let before_time = Instant::now();
 
         let processes_arc = self.processes.clone();
-        let mut processes_guard = processes_arc.write().await;
+        let processes_guard = processes_arc.read().await;
 
         //extract child PID from the processes
-        let child = processes_guard.get_mut(&id);
+        let process = processes_guard.get(&id);
 
         println!(
             "kill: After getting of child from the process list time: {:?}",
             Instant::now().duration_since(before_time)
         );
 
-        if child.is_none() {
-            let mut result = OldKillResult::new();
+        let mut result = KillResult::new();
+        if process.is_none() {
             result.set_error("Child not found PID for SIGTERM sending".to_owned());
             return result;
         }
-        let child = child.unwrap();
-        let pid: i32 = child.id() as i32;
+        let process = process.unwrap();
+        if process.is_finished() {
+            result.set_success(process.exit_status().and_then(|status| status.code()));
+            return result;
+        }
+        let chain = self.escalation_chain(process.mode());
+        //the first signal could be sent already by terminate(), then the escalation is scheduled
+        let is_first_signal_needed = matches!(
+            process.state(),
+            LifecycleState::Running | LifecycleState::Paused
+        );
 
         drop(processes_guard);
 
-        println!(
-            "kill: After dropping time: {:?}",
-            Instant::now().duration_since(before_time)
-        );
+        if is_first_signal_needed {
+            let terminate_result = self.terminate(id.clone()).await;
+            if let Some(e) = terminate_result.error_message() {
+                result.set_error(e.clone());
+                return result;
+            }
+        }
 
-        println!("kill: Sending SIGTERM to PID: {}", pid);
-        signal::kill(Pid::from_raw(pid), signal::SIGTERM).unwrap();
-        println!(
-            "kill: After SIGTERM sending: {:?}",
-            Instant::now().duration_since(before_time)
-        );
-        let duration = Duration::from_secs(self.sig_term_timeout);
-        let deadline = Instant::now() + duration;
-        println!("kill: Sleeping until: {:?}", deadline);
-        sleep_until(deadline).await;
+        //the kill queue follows the chain, process_states() reports the process
+        let timeout = chain.total_wait() + SIGKILL_EXIT_TIMEOUT;
+        match tokio::time::timeout(timeout, self.wait_for_exit(&id)).await {
+            Ok(status) => result.set_success(status.and_then(|status| status.code())),
+            Err(_) => result.set_error(format!("Process {} did not exit in {:?}", id, timeout)),
+        }
         println!(
-            "kill: After sleep_until awaiting: {:?}",
+            "kill: After escalation: {:?}",
             Instant::now().duration_since(before_time)
         );
+        result
+    }
 
-        let mut result = OldKillResult::new();
-        //after SIGTERM timeout we should send SIGKILL signal to make sure the process will be terminated
-        let processes_arc = self.processes.clone();
-        println!(
-            "kill: After new processes_arc cloning: {:?}",
-            Instant::now().duration_since(before_time)
-        );
-        let mut processes_guard = processes_arc.write().await;
-        println!(
-            "kill: After new processes_guard awaiting: {:?}",
-            Instant::now().duration_since(before_time)
-        );
-        println!(
-            "kill: After new result_guard awaiting: {:?}",
-            Instant::now().duration_since(before_time)
-        );
-        //extract child PID from the processes
-        let child = processes_guard.get_mut(&id);
-        if child.is_none() {
-            result.set_error("Child not found PID for SIGKILL sending".to_owned());
+    ///terminates every selected process, see terminate()
+    pub async fn terminate_selected(
+        &self,
+        selector: &ProcessSelector,
+    ) -> Vec<SelectedProcessResult> {
+        let (selected_ids, rejected) = self.select_processes(selector).await;
+        println!("Terminating selected processes: {:?}", selected_ids);
+        let mut results = vec![];
+        for id in selected_ids {
+            let result = self.terminate(id.clone()).await;
+            results.push(match result.error_message() {
+                None => SelectedProcessResult::success(id, None),
+                Some(e) => SelectedProcessResult::error(id, e.clone()),
+            });
+        }
+        results.extend(rejected);
+        results
+    }
+
+    ///kills every selected process concurrently and waits for all of them, see kill()
+    pub async fn kill_selected(&self, selector: &ProcessSelector) -> Vec<SelectedProcessResult> {
+        let (selected_ids, rejected) = self.select_processes(selector).await;
+        println!("Killing selected processes: {:?}", selected_ids);
+        let kills = selected_ids.into_iter().map(|id| async move {
+            let result = self.kill(id.clone()).await;
+            match result.error_message() {
+                None => SelectedProcessResult::success(id, result.exit_code()),
+                Some(e) => SelectedProcessResult::error(id, e.clone()),
+            }
+        });
+        let mut results = join_all(kills).await;
+        results.extend(rejected);
+        results
+    }
+
+    //returns the sorted ids of not finished processes matching the selector and the error results
+    //of the explicitly selected ids which are not supervised or already finished
+    async fn select_processes(
+        &self,
+        selector: &ProcessSelector,
+    ) -> (Vec<String>, Vec<SelectedProcessResult>) {
+        let ps_g = self.processes.read().await;
+        let mut selected_ids: Vec<String> = ps_g
+            .iter()
+            .filter(|(id, process)| !process.is_finished() && selector.matches(id, process))
+            .map(|(id, _)| id.clone())
+            .collect();
+        selected_ids.sort();
+        let rejected = selector
+            .ids
+            .iter()
+            .flatten()
+            .filter_map(|id| match ps_g.get(id) {
+                None => Some(not_found_result(id.clone())),
+                Some(process) if process.is_finished() => Some(finished_result(id.clone())),
+                Some(_) => None,
+            })
+            .collect();
+        (selected_ids, rejected)
+    }
+
+    ///stops the process group with SIGSTOP, the process deadline does not run while it is paused
+    pub async fn pause(&self, id: String) -> PauseResult {
+        self.change_pause_state(&id, LifecycleState::Paused, Signal::SIGSTOP)
+            .await
+    }
+
+    ///continues a paused process group with SIGCONT
+    pub async fn resume(&self, id: String) -> PauseResult {
+        self.change_pause_state(&id, LifecycleState::Running, Signal::SIGCONT)
+            .await
+    }
+
+    async fn change_pause_state(
+        &self,
+        id: &str,
+        to: LifecycleState,
+        signal: Signal,
+    ) -> PauseResult {
+        let mut result = PauseResult::new();
+        let mut processes_guard = self.processes.write().await;
+        let process = match processes_guard.get_mut(id) {
+            Some(process) => process,
+            None => {
+                result.set_error(format!("Process {} is not supervised", id));
+                return result;
+            }
+        };
+        if process.is_finished() {
+            //the PID could be reused already
+            result.set_error(format!("Process {} is already finished", id));
             return result;
         }
-        let child = child.unwrap();
+        if let Err(e) = process.lifecycle().check_transition(to) {
+            result.set_error(format!("Process {} can't be {}: {}", id, to.as_str(), e));
+            return result;
+        }
+        //the state changes only once the signal is delivered, the lock keeps the check valid
+        println!("Sending {} to PID: {}", signal, process.pid);
+        if let Err(e) = process_group::signal_group(process.pid, signal) {
+            result.set_error(e.to_string());
+            return result;
+        }
+        if let Err(e) = process.transition(to) {
+            println!("Process {}: {}", id, e);
+        }
+        result.set_success();
+        result
+    }
 
-        //send SIGKILL (9) signal
-        println!("Sending SIGKILL to PID: {}", pid);
-        let kill_result = child.kill();
-        println!(
-            "After kill time: {:?}",
-            Instant::now().duration_since(before_time)
-        );
+    ///supervisor-wide pause: stops every running process and the launch of new ones
+    pub async fn pause_all(&self) -> Vec<SelectedProcessResult> {
+        self.is_pause_mode.send_replace(true);
+        let ids = self.ids_in_state(LifecycleState::Running).await;
+        let mut results = vec![];
+        for id in ids {
+            results.push(pause_result(id.clone(), self.pause(id).await));
+        }
+        results
+    }
 
-        match kill_result {
-            Ok(_) => {
-                let exit_status = child.try_wait();
-                println!(
-                    "kill: After try_wait time: {:?}",
-                    Instant::now().duration_since(before_time)
-                );
+    ///resumes every paused process, including the ones paused one by one, and the launches
+    pub async fn resume_all(&self) -> Vec<SelectedProcessResult> {
+        self.is_pause_mode.send_replace(false);
+        let ids = self.ids_in_state(LifecycleState::Paused).await;
+        let mut results = vec![];
+        for id in ids {
+            results.push(pause_result(id.clone(), self.resume(id).await));
+        }
+        results
+    }
 
-                let ch = processes_guard.remove(&id);
-                println!(
-                    "After remove time: {:?}",
-                    Instant::now().duration_since(before_time)
-                );
-                if ch.is_none() {
-                    println!("Failed to remove child {} from processes", id);
-                }
-                drop(processes_guard);
-
-                match exit_status {
-                    Ok(status) => {
-                        match status {
-                            Some(_) => {
-                                println!("Status code; {:?}", status.unwrap().code());
-                                result.set_success(status.unwrap().code());
-                            }
-                            None => {
-                                //probably, the process was finished before the killing signal sending
-                                result.set_success(Some(9999999));
-                            }
-                        };
-                    }
-                    Err(e) => {
-                        println!("Error: {}", e);
-                        result.set_error(e.to_string());
-                    }
-                }
+    ///validates the signal name against SIGNAL_ALLOWLIST
+    pub fn allowed_signal(&self, name: &str) -> Result<Signal, String> {
+        let signal = parse_signal(name)?;
+        if !self.signal_allowlist.contains(&signal) {
+            return Err(format!("{} is not in SIGNAL_ALLOWLIST", signal));
+        }
+        Ok(signal)
+    }
+
+    ///sends the signal to the process group and records the delivery in the process events
+    pub async fn send_signal(&self, id: String, signal: Signal) -> SignalResult {
+        let mut result = SignalResult::new();
+        let mut processes_guard = self.processes.write().await;
+        let process = match processes_guard.get_mut(&id) {
+            Some(process) => process,
+            None => {
+                result.set_error(format!("Process {} is not supervised", id));
+                return result;
             }
-            Err(e) => {
-                result.set_error(e.to_string());
+        };
+        if process.is_finished() {
+            //the PID could be reused already
+            result.set_error(format!("Process {} is already finished", id));
+            return result;
+        }
+        println!("Sending {} to PID: {}", signal, process.pid);
+        match process_group::signal_group(process.pid, signal) {
+            Ok(_) => {
+                process.add_event(ProcessEventKind::SignalSent, format!("{} is sent", signal));
+                result.set_success();
             }
+            Err(e) => result.set_error(e.to_string()),
         }
         result
     }
 
-    pub async fn kill(&self, id: String, terminate_signal_time: u64) -> KillResult {
-        let now = SystemTime::now();
-        let wait_time_elapsed = now
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            - terminate_signal_time;
-
-        if wait_time_elapsed < self.sig_term_timeout {
-            println!("There is some time left before SIGKILL sending. Sleeping...");
-            let sleep_time = self.sig_term_timeout - wait_time_elapsed;
-            println!("kill: Sleeping seconds: {:?}", sleep_time);
-            sleep(Duration::from_secs(sleep_time)).await;
-            println!("kill: After sleep awaiting: {:?}", now.elapsed());
+    ///sends the signal to every process which is not finished yet
+    pub async fn send_signal_to_all(&self, signal: Signal) -> Vec<SelectedProcessResult> {
+        let mut ids: Vec<String> = self
+            .processes
+            .read()
+            .await
+            .iter()
+            .filter(|(_, process)| !process.is_finished())
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        let mut results = vec![];
+        for id in ids {
+            let result = self.send_signal(id.clone(), signal).await;
+            results.push(match result.error_message() {
+                None => SelectedProcessResult::success(id, None),
+                Some(e) => SelectedProcessResult::error(id, e.clone()),
+            });
         }
+        results
+    }
 
-        let before_time = Instant::now();
+    async fn ids_in_state(&self, state: LifecycleState) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .processes
+            .read()
+            .await
+            .iter()
+            .filter(|(_, process)| process.state() == state)
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
 
-        let mut result = KillResult::new();
-        //after SIGTERM timeout we should send SIGKILL signal to make sure the process will be terminated
-        let processes_arc = self.processes.clone();
+    //schedules the step following the sent one, nothing is left after SIGKILL
+    async fn schedule_next_step(
+        &self,
+        id: &str,
+        pid: u32,
+        chain: &EscalationChain,
+        sent_step: usize,
+    ) {
+        let wait = match chain.steps()[sent_step].wait {
+            Some(wait) => wait,
+            None => return,
+        };
+        let step = sent_step + 1;
         println!(
-            "kill: After new processes_arc cloning: {:?}",
-            Instant::now().duration_since(before_time)
+            "kill: {} is due for process {} in {:?}",
+            chain.steps()[step].signal,
+            id,
+            wait
         );
-        let mut processes_guard = processes_arc.write().await;
-        println!(
-            "kill: After new processes_guard awaiting: {:?}",
-            Instant::now().duration_since(before_time)
+        self.kill_queue.write().await.schedule(
+            Instant::now() + wait,
+            ScheduledStep {
+                id: id.to_string(),
+                pid,
+                step,
+            },
         );
-        //extract child PID from the processes
-        let child = processes_guard.get_mut(&id);
-        if child.is_none() {
-            result.set_error("Child not found PID for SIGKILL sending.".to_owned());
-            return result;
+        self.kill_queue_changed.notify_one();
+    }
+
+    ///fires every escalation step at its deadline. Each step runs in its own task,
+    ///so processes terminated together are not delayed by each other
+    pub async fn run_kill_queue(&self) {
+        loop {
+            let next_deadline = self.kill_queue.read().await.next_deadline();
+            match next_deadline {
+                Some(deadline) => {
+                    tokio::select! {
+                        _ = sleep_until(deadline) => {}
+                        _ = self.kill_queue_changed.notified() => {}
+                    }
+                }
+                None => self.kill_queue_changed.notified().await,
+            }
+
+            let due = self.kill_queue.write().await.pop_due(Instant::now());
+            for scheduled in due {
+                let supervisor = self.clone();
+                task::spawn(async move {
+                    supervisor.run_escalation_step(scheduled).await;
+                });
+            }
+        }
+    }
+
+    //sends the scheduled step if the process is still running and schedules the next one
+    async fn run_escalation_step(&self, scheduled: ScheduledStep) {
+        let ScheduledStep { id, pid, step } = scheduled;
+        let processes_guard = self.processes.read().await;
+        let process = match processes_guard.get(&id) {
+            Some(process) if !process.is_finished() && process.pid == pid => process,
+            //the process exited meanwhile (and could be relaunched under the same id),
+            //the rest of the chain is not needed
+            _ => return,
+        };
+        let chain = self.escalation_chain(process.mode());
+        drop(processes_guard);
+
+        let signal = match chain.steps().get(step) {
+            Some(escalation_step) => escalation_step.signal,
+            None => return,
+        };
+        if signal == Signal::SIGKILL {
+            //process_states() reports the process and cleans up the processes list
+            if let Err(e) = self.send_sigkill(&id, pid).await {
+                println!("Failed to kill process {}: {}", id, e);
+            }
+            return;
         }
-        let child = child.unwrap();
 
-        let state = get_child_state(id.clone(), child).unwrap();
-        if state.is_finished {
+        println!("kill: Sending {} to PID: {}", signal, pid);
+        if let Err(e) = process_group::signal_group(pid, signal) {
+            println!("Failed to send {} to process {}: {}", signal, id, e);
+            return;
+        }
+        self.schedule_next_step(&id, pid, &chain, step).await;
+    }
+
+    //sends SIGKILL to the process launched with the PID if it is still running and waits
+    //for its exit
+    async fn send_sigkill(&self, id: &str, pid: u32) -> Result<(), String> {
+        let before_time = Instant::now();
+        let mut processes_guard = self.processes.write().await;
+        let process = processes_guard
+            .get_mut(id)
+            .filter(|process| process.pid == pid)
+            .ok_or("Child not found PID for SIGKILL sending.")?;
+        if let Some(status) = process.exit_status() {
             println!(
                 "It seems the process finished itself. Exit code: {:?}",
-                state.exit_code
+                status.code()
             );
-            result.set_success(state.exit_code);
-            //we use process_states() to clean up the processes list
-            // let ch = processes_guard.remove(&id);
-            // println!(
-            //     "After child remove time: {:?}",
-            //     Instant::now().duration_since(before_time)
-            // );
-            // if ch.is_none() {
-            //     println!("Failed to remove child for process {}", id);
-            // }
-            return result;
+            return Ok(());
+        }
+        //a single step chain sends SIGKILL as its first signal already
+        let is_killing = process.state() == LifecycleState::Killing;
+        if !is_killing {
+            process
+                .lifecycle()
+                .check_transition(LifecycleState::Killing)
+                .map_err(|e| format!("Process {} can't be killed: {}", id, e))?;
         }
 
-        //send SIGKILL (9) signal
-        println!("Sending SIGKILL to PID: {}", child.id());
-        let kill_result = child.kill();
+        //send SIGKILL (9) signal, the state changes only once it is delivered
+        println!("Sending SIGKILL to PID: {}", pid);
+        process_group::signal_group(pid, signal::SIGKILL).map_err(|e| e.to_string())?;
+        if !is_killing {
+            if let Err(e) = process.transition(LifecycleState::Killing) {
+                println!("Process {}: {}", id, e);
+            }
+        }
+        drop(processes_guard);
         println!(
             "After kill time: {:?}",
             Instant::now().duration_since(before_time)
         );
 
-        match kill_result {
-            Ok(_) => {
-                let exit_status = child.try_wait();
-                println!(
-                    "kill: After try_wait time: {:?}",
-                    Instant::now().duration_since(before_time)
-                );
+        match tokio::time::timeout(SIGKILL_EXIT_TIMEOUT, self.wait_for_exit(id)).await {
+            Ok(Some(status)) => println!("Status code; {:?}", status.code()),
+            //probably, the process is not reaped yet
+            _ => println!(
+                "Process {} did not exit within {:?} after SIGKILL",
+                id, SIGKILL_EXIT_TIMEOUT
+            ),
+        }
+        Ok(())
+    }
 
-                //we use process_states() to clean up the processes list
-                // let ch = processes_guard.remove(&id);
-                // println!(
-                //     "After child remove time: {:?}",
-                //     Instant::now().duration_since(before_time)
-                // );
-                // if ch.is_none() {
-                //     println!("Failed to remove child for processes {}", id);
-                // }
-                // drop(processes_guard);
-
-                match exit_status {
-                    Ok(status) => {
-                        match status {
-                            Some(_) => {
-                                println!("Status code; {:?}", status.unwrap().code());
-                                result.set_success(status.unwrap().code());
-                            }
-                            None => {
-                                //probably, the process was finished before the killing signal sending
-                                result.set_success(Some(9999999));
-                            }
-                        };
-                    }
-                    Err(e) => {
-                        println!("Error: {}", e);
-                        result.set_error(e.to_string());
-                    }
-                };
-            }
-            Err(e) => {
-                result.set_error(e.to_string());
+    ///waits until the process exits, returns None if there is no such process
+    async fn wait_for_exit(&self, id: &str) -> Option<ExitStatus> {
+        let mut receiver = self.processes.read().await.get(id)?.exit_status_receiver();
+        let status = receiver.wait_for(|status| status.is_some()).await.ok()?;
+        *status
+    }
+
+    //the profile chain or SIGTERM followed by SIGKILL after the SIGTERM timeout
+    fn escalation_chain(&self, mode: ProcessingMode) -> EscalationChain {
+        match self.worker_profiles.for_mode(mode).termination_chain() {
+            Some(chain) => chain.clone(),
+            None => {
+                EscalationChain::sigterm_then_sigkill(Duration::from_secs(self.sig_term_timeout))
             }
         }
+    }
 
-        result
+    ///the longest time from the first signal to SIGKILL among all profiles
+    pub fn max_escalation_duration(&self) -> Duration {
+        [ProcessingMode::Regular, ProcessingMode::Sandbox]
+            .into_iter()
+            .map(|mode| self.escalation_chain(mode).total_wait())
+            .max()
+            .unwrap_or_default()
+    }
+
+    ///returns a notifier woken up every time a child exits
+    pub fn child_exit_notifier(&self) -> Arc<Notify> {
+        Arc::clone(&self.child_exited)
     }
 
     pub async fn get_state_list(self: Arc<Self>) -> HashMap<String, ChildState> {
@@ -407,11 +1088,23 @@ impl Supervisor {
                         println!("get_child_state returned error: {}", e);
                         ChildState {
                             id: id.clone(),
+                            pid: 0,
+                            attempt: 0,
+                            state: LifecycleState::Reported,
+                            transitions: vec![],
+                            paused_secs: 0,
                             is_running: false,
                             is_finished: false,
                             exit_code: None,
+                            signal: None,
+                            core_dumped: false,
                             is_killed: false,
                             rss_anon_memory_kb: None,
+                            metrics: None,
+                            alive_descendant_pids: vec![],
+                            cgroup_usage: None,
+                            termination_reason: None,
+                            events: vec![],
                         }
                     })
                 })
@@ -461,41 +1154,151 @@ impl Supervisor {
             "get_process_state: After self.processes.clone(), time: {:?}",
             Instant::now().duration_since(before_time)
         );
-        let mut processes_guard = processes_arc.write().await;
+        let processes_guard = processes_arc.read().await;
         println!(
-            "get_process_state: After processes_arc.write().await, time: {:?}",
+            "get_process_state: After processes_arc.read().await, time: {:?}",
             Instant::now().duration_since(before_time)
         );
-        let child = processes_guard
-            .get_mut(&id)
+        let process = processes_guard
+            .get(&id)
             .ok_or_else(|| Error::new(std::io::ErrorKind::NotFound, "Child not found"))?;
         println!(
-            "get_process_state: After processes_guard.get_mut, time: {:?}",
+            "get_process_state: After processes_guard.get, time: {:?}",
             Instant::now().duration_since(before_time)
         );
 
-        get_child_state(id, child)
+        Ok(get_child_state(id, process))
     }
 
-    pub async fn process_kill_queue(&self) {
-        let option: Option<(String, u64)> = self.pop_kill_queue().await;
-        if option.is_none() {
-            return;
+    ///checks the running processes against their profile limits: a process running longer
+    ///than its deadline or exceeding the memory hard limit is terminated, the memory soft
+    ///limit is only recorded
+    pub async fn run_watchdog(&self) {
+        let ps_g = self.processes.read().await;
+        let candidates: Vec<(String, u32, ProcessingMode, Duration, Option<u64>)> = ps_g
+            .iter()
+            //a process being stopped (or already stopped by an operator) is not checked again
+            .filter(|(_, process)| {
+                matches!(
+                    process.state(),
+                    LifecycleState::Running | LifecycleState::Paused
+                ) && process.termination_reason().is_none()
+            })
+            .map(|(id, process)| {
+                (
+                    id.clone(),
+                    process.pid,
+                    process.mode(),
+                    process.active_runtime(),
+                    process.assigned_process.max_runtime_secs,
+                )
+            })
+            .collect();
+        drop(ps_g);
+
+        let mut exceeded_ids = vec![];
+        for (id, pid, mode, runtime, assigned_max_runtime_secs) in candidates {
+            let profile = self.worker_profiles.for_mode(mode);
+
+            //the dispatcher deadline has priority over the profile one
+            let max_runtime_secs = assigned_max_runtime_secs.or(profile.max_runtime_secs());
+            if let Some(max_runtime_secs) = max_runtime_secs {
+                if runtime.as_secs() >= max_runtime_secs {
+                    let message = format!(
+                        "runtime {}s reached the deadline {}s, terminating",
+                        runtime.as_secs(),
+                        max_runtime_secs
+                    );
+                    if self
+                        .record_limit_exceeded(
+                            &id,
+                            ProcessEventKind::DeadlineReached,
+                            message,
+                            Some(TerminationReason::Timeout),
+                        )
+                        .await
+                    {
+                        exceeded_ids.push(id);
+                    }
+                    continue;
+                }
+            }
+
+            let limits = profile.memory_limits();
+            if limits.is_empty() {
+                continue;
+            }
+            let memory_kb = match sample_memory_kb(pid) {
+                Some(memory_kb) => memory_kb,
+                None => continue,
+            };
+            match (limits.soft_limit_kb, limits.hard_limit_kb) {
+                (_, Some(hard_limit_kb)) if memory_kb > hard_limit_kb => {
+                    let message = format!(
+                        "memory usage {} KB exceeds the hard limit {} KB, terminating",
+                        memory_kb, hard_limit_kb
+                    );
+                    if self
+                        .record_limit_exceeded(
+                            &id,
+                            ProcessEventKind::MemoryHardLimitExceeded,
+                            message,
+                            Some(TerminationReason::MemoryLimitExceeded),
+                        )
+                        .await
+                    {
+                        exceeded_ids.push(id);
+                    }
+                }
+                (Some(soft_limit_kb), _) if memory_kb > soft_limit_kb => {
+                    let message = format!(
+                        "memory usage {} KB exceeds the soft limit {} KB",
+                        memory_kb, soft_limit_kb
+                    );
+                    self.record_limit_exceeded(
+                        &id,
+                        ProcessEventKind::MemorySoftLimitExceeded,
+                        message,
+                        None,
+                    )
+                    .await;
+                }
+                _ => {}
+            }
         }
 
-        let (id, terminate_signal_time) = option.unwrap();
-        self.kill(id, terminate_signal_time).await;
+        //the first signal now, the kill queue escalates if the process ignores it
+        for id in exceeded_ids {
+            let result = self.terminate(id.clone()).await;
+            if !result.is_success() {
+                println!(
+                    "Failed to terminate process {} over its limits: {:?}",
+                    id,
+                    result.error_message()
+                );
+            }
+        }
     }
 
-    pub async fn pop_kill_queue(&self) -> Option<(String, u64)> {
-        let mut kill_queue_guard = self.kill_queue.write().await;
-        let (id, terminate_signal_time) = kill_queue_guard
-            .iter()
-            .next()
-            .map(|(k, v)| (k.clone(), *v))?;
-        kill_queue_guard.remove(&id);
-        // drop(kill_queue_guard);
-        Some((id, terminate_signal_time))
+    //records the limit event once per process and sets the termination reason if given.
+    //Returns false if the process exited meanwhile or the event is recorded already
+    async fn record_limit_exceeded(
+        &self,
+        id: &str,
+        kind: ProcessEventKind,
+        message: String,
+        termination_reason: Option<TerminationReason>,
+    ) -> bool {
+        let mut ps_g = self.processes.write().await;
+        let process = match ps_g.get_mut(id) {
+            Some(process) if !process.is_finished() && !process.has_event(kind) => process,
+            _ => return false,
+        };
+        process.add_event(kind, message);
+        if let Some(reason) = termination_reason {
+            process.set_termination_reason(reason);
+        }
+        true
     }
 
     //cleans up the processes list from finished processes and returns the number of processes left
@@ -510,38 +1313,74 @@ impl Supervisor {
         let mut working_processes_cnt = ids.len();
         for id in ids {
             let mut ps_g = ps_arc.write().await;
-            let child = ps_g.get_mut(&id);
-            if child.is_none() {
+            let process = ps_g.get_mut(&id);
+            if process.is_none() {
                 working_processes_cnt -= 1;
                 println!("Child {} not found in the process list", id.clone());
                 drop(ps_g);
                 continue;
             }
 
-            let state = get_child_state(id.clone(), child.unwrap());
-            drop(ps_g);
-            if state.is_err() {
-                println!("Error getting child state: {}", state.err().unwrap());
+            let process = process.unwrap();
+            if process.state() == LifecycleState::Exited {
+                self.track_crash_loop(process).await;
+            }
+            if process.state() == LifecycleState::Exited && self.is_retryable(process) {
+                //the dispatcher hears only about the final attempt
+                let process = ps_g.remove(&id).unwrap();
+                drop(ps_g);
+                working_processes_cnt -= 1;
+                self.schedule_retry(process).await;
                 continue;
             }
-            let state = state.unwrap();
+            //a process whose report failed stays in the reporting state until the report succeeds
+            if process.state() == LifecycleState::Exited {
+                if let Err(e) = process.transition(LifecycleState::Reporting) {
+                    println!("Process {}: {}", id, e);
+                }
+            }
+            let pgid = process.pid;
+            let state = get_child_state(id.clone(), process);
+            drop(ps_g);
 
             if !state.is_finished {
                 println!("Process {} is still running.", id);
                 continue;
             }
 
+            if !state.alive_descendant_pids.is_empty() {
+                //the worker is gone, its leftovers must not outlive the slot
+                println!(
+                    "Process {} left alive descendants {:?}. Sending SIGKILL to the group...",
+                    id, state.alive_descendant_pids
+                );
+                if let Err(e) = process_group::signal_group(pgid, signal::SIGKILL) {
+                    println!("Failed to kill process group {}: {}", pgid, e);
+                }
+            }
+
             println!(
-                "Process {} finished with exit code: {:?}. Reporting to the dispatcher...",
-                id, state.exit_code
+                "Process {} finished with exit code: {:?}, signal: {:?}, core dumped: {}. Reporting to the dispatcher...",
+                id, state.exit_code, state.signal, state.core_dumped
             );
-            let exit_code = state.exit_code.unwrap_or(0);
             //TODO: report kill status to dispatcher
-            let process_result = match exit_code {
-                0 => dispatcher::REPORT_STATUS_SUCCESS.to_string(),
-                _ => dispatcher::REPORT_STATUS_ERROR.to_string(),
+            //there is no exit code if the process was killed by a signal, it is never a success.
+            //A process stopped by the supervisor is never a success either, even if it exited gracefully
+            let is_success = matches!((state.exit_code, state.termination_reason), (Some(0), None));
+            let process_result = match is_success {
+                true => dispatcher::REPORT_STATUS_SUCCESS.to_string(),
+                false => dispatcher::REPORT_STATUS_ERROR.to_string(),
             };
-            let report = dispatcher::ProcessFinishReport::new(id.clone(), process_result);
+            let report = dispatcher::ProcessFinishReport::new(
+                id.clone(),
+                process_result,
+                state
+                    .termination_reason
+                    .map(|reason| reason.as_str().to_string()),
+                state.signal,
+                state.core_dumped,
+                state.attempt,
+            );
             let report_result = self.dispatcher_client.report_process_finish(report).await;
             if report_result.is_err() {
                 println!("Failed to report process finish: {:?}", report_result.err());
@@ -550,13 +1389,145 @@ impl Supervisor {
             }
             println!("Process {:?} finish reported successfully. Removing...", id);
             let mut ps_g = ps_arc.write().await;
-            ps_g.remove(&id);
+            if let Some(process) = ps_g.get_mut(&id) {
+                if let Err(e) = process.transition(LifecycleState::Reported) {
+                    println!("Process {}: {}", id, e);
+                }
+            }
+            let removed = ps_g.remove(&id);
             working_processes_cnt -= 1;
             drop(ps_g);
+            if let Some(cgroup) = removed.and_then(|process| process.cgroup) {
+                cgroup.remove().await;
+            }
+            self.remove_launch_files(&id).await;
+            self.release_workdir(&id, is_success).await;
+            self.release_logs(&id).await;
             println!("Process {:?} removed successfully.", id);
         }
+        self.purge_expired_logs().await;
         println!("Child states processing is finished.");
+        //deferred and retried processes are not running now, but they are ours to finish as well
         working_processes_cnt
+            + self.deferred_processes.read().await.len()
+            + self.pending_retries.read().await.len()
+    }
+
+    //a failed process which exited right after its launch means the worker is probably broken
+    async fn track_crash_loop(&self, process: &SupervisedProcess) {
+        let lifecycle = process.lifecycle();
+        let runtime = match (
+            lifecycle.entered_at(LifecycleState::Running),
+            lifecycle.entered_at(LifecycleState::Exited),
+        ) {
+            (Some(running_at), Some(exited_at)) => (exited_at - running_at).to_std().ok(),
+            _ => None,
+        };
+        let runtime = match runtime {
+            Some(runtime) => runtime,
+            None => return,
+        };
+        let mut crash_loop_guard = self.crash_loop.write().await;
+        if runtime >= crash_loop_guard.min_runtime() {
+            crash_loop_guard.record_healthy_run();
+            return;
+        }
+        let exit_status = process.exit_status();
+        let is_failed = exit_status.and_then(|status| status.code()) != Some(0);
+        if is_failed && process.termination_reason().is_none() {
+            crash_loop_guard.record_failure(format!(
+                "process {} exited after {:?}: {}",
+                process.assigned_process.id,
+                runtime,
+                exit_status
+                    .map(|status| status.to_string())
+                    .unwrap_or_default()
+            ));
+        }
+    }
+
+    fn is_retryable(&self, process: &SupervisedProcess) -> bool {
+        //processes stopped by the supervisor or an operator (e.g. via /terminate) are not retried
+        let lifecycle = process.lifecycle();
+        process.termination_reason().is_none()
+            && !lifecycle.has_been(LifecycleState::Terminating)
+            && !lifecycle.has_been(LifecycleState::Killing)
+            && self.retry_policy.should_retry(
+                process.attempt,
+                process.exit_status().and_then(|status| status.code()),
+            )
+    }
+
+    //cleans up after the failed attempt and relaunches the process under the same id after a backoff
+    async fn schedule_retry(&self, process: SupervisedProcess) {
+        let id = process.assigned_process.id.clone();
+        let attempt = process.attempt + 1;
+        let backoff = self.retry_policy.backoff(attempt);
+        println!(
+            "Process {} failed with exit code {:?}. Retrying in {:?}, attempt {}...",
+            id,
+            process.exit_status().and_then(|status| status.code()),
+            backoff,
+            attempt
+        );
+        if !process_group::alive_descendants(process.pid).is_empty() {
+            if let Err(e) = process_group::signal_group(process.pid, signal::SIGKILL) {
+                println!("Failed to kill process group {}: {}", process.pid, e);
+            }
+        }
+        if let Some(cgroup) = &process.cgroup {
+            cgroup.remove().await;
+        }
+        self.remove_launch_files(&id).await;
+        self.pending_retries
+            .write()
+            .await
+            .insert(id.clone(), attempt);
+
+        let supervisor = self.clone();
+        let assigned_process = process.assigned_process;
+        task::spawn(async move {
+            let mut terminate_mode = supervisor.is_terminate_mode.subscribe();
+            tokio::select! {
+                _ = sleep(backoff) => {}
+                _ = terminate_mode.wait_for(|is_terminate_mode| *is_terminate_mode) => {}
+            }
+            //a supervisor-wide pause postpones the relaunch until resumed
+            let mut pause_mode = supervisor.is_pause_mode.subscribe();
+            tokio::select! {
+                _ = pause_mode.wait_for(|is_pause_mode| !*is_pause_mode) => {}
+                _ = terminate_mode.wait_for(|is_terminate_mode| *is_terminate_mode) => {}
+            }
+            if supervisor.is_terminate_mode().await {
+                println!("Terminate mode is on. Process {} is not relaunched", id);
+                //the last attempt is over already
+                supervisor
+                    .report_not_running(&id, Some(TerminationReason::Interrupted), attempt - 1)
+                    .await;
+                supervisor.pending_retries.write().await.remove(&id);
+                //the previous attempt failed
+                supervisor.release_workdir(&id, false).await;
+                supervisor.release_logs(&id).await;
+                return;
+            }
+            let result = supervisor.launch_attempt(assigned_process, attempt).await;
+            //removed after the launch, so the slot is never seen as free in between
+            supervisor.pending_retries.write().await.remove(&id);
+            if result.is_success() {
+                println!("Process {} relaunched, attempt {}", id, attempt);
+                return;
+            }
+            println!(
+                "Failed to relaunch process {}: {:?}",
+                id,
+                result.error_message()
+            );
+            //a rejected process is reported by launch_attempt()
+            if !result.is_rejected() {
+                supervisor.report_not_running(&id, None, attempt).await;
+            }
+            supervisor.release_logs(&id).await;
+        });
     }
 
     ///if empty processed slots exist, fetches new processes from dispatcher and run them
@@ -567,10 +1538,23 @@ impl Supervisor {
         if *is_drain_mode_guard {
             return Err(SlotsPopulationError::DrainModeObtained);
         }
+        drop(is_drain_mode_guard);
+
+        if self.is_terminate_mode().await {
+            return Ok(());
+        }
+
+        if self.is_population_paused().await {
+            return Ok(());
+        }
+
+        self.launch_deferred_processes().await;
 
         let processes_arc = self.processes.clone();
         let processes_guard = processes_arc.read().await;
-        let processes_count = processes_guard.len();
+        //deferred processes wait for a slot of their processing mode and do not occupy
+        //a slot themselves, so a sandbox backlog does not keep regular slots empty
+        let processes_count = processes_guard.len() + self.pending_retries.read().await.len();
         drop(processes_guard);
 
         if processes_count >= self.max_children_count {
@@ -594,6 +1578,17 @@ impl Supervisor {
                 return Err(SlotsPopulationError::DrainModeObtained);
             }
 
+            //launches in this round could have failed as well
+            if self.is_population_paused().await {
+                return Ok(());
+            }
+
+            //obtained processes are ours already, so their backlog is bounded
+            if self.deferred_processes.read().await.len() >= self.max_children_count {
+                println!("Deferred processes backlog is full. Waiting for free slots...");
+                break;
+            }
+
             println!("Sleeping...");
             tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
 
@@ -604,14 +1599,25 @@ impl Supervisor {
                 continue;
             }
             let assigned_process = assigned_process.unwrap();
-            // let supervisor = self.clone();
-            // let result = supervisor.launch(new_process.id.clone()).await;
-            let result = self.launch(assigned_process.id.clone()).await;
-            // drop(supervisor);
+            let id = assigned_process.id.clone();
+            let source_id = assigned_process.source_id;
+            if !self.has_free_slot(assigned_process.mode).await {
+                println!(
+                    "No free {} slots for process {:?}. Deferring its launch...",
+                    assigned_process.mode.as_str(),
+                    id
+                );
+                self.deferred_processes
+                    .write()
+                    .await
+                    .push_back(assigned_process);
+                continue;
+            }
+            let result = self.launch(assigned_process).await;
             if result.is_success() {
                 println!(
                     "Process {:?} for source {:?} launched successfully",
-                    assigned_process.id, assigned_process.source_id
+                    id, source_id
                 );
                 continue;
             }
@@ -621,6 +1627,161 @@ impl Supervisor {
         Ok(())
     }
 
+    async fn is_population_paused(&self) -> bool {
+        if self.is_pause_mode().await {
+            println!("Slots population is paused until the supervisor is resumed");
+            return true;
+        }
+        let crash_loop_guard = self.crash_loop.read().await;
+        if !crash_loop_guard.is_paused() {
+            return false;
+        }
+        println!(
+            "Slots population is paused: {:?}",
+            crash_loop_guard.status()
+        );
+        true
+    }
+
+    pub async fn status(&self) -> SupervisorStatus {
+        SupervisorStatus {
+            is_drain_mode: self.is_drain_mode().await,
+            is_terminate_mode: self.is_terminate_mode().await,
+            is_pause_mode: self.is_pause_mode().await,
+            processes_count: self.processes.read().await.len(),
+            deferred_processes_count: self.deferred_processes.read().await.len(),
+            pending_retries_count: self.pending_retries.read().await.len(),
+            pending_kill_steps_count: self.kill_queue.read().await.len(),
+            crash_loop: self.crash_loop.read().await.status(),
+        }
+    }
+
+    ///launches previously deferred processes whose processing mode got a free slot
+    pub async fn launch_deferred_processes(&self) {
+        if self.is_pause_mode().await {
+            return;
+        }
+        let mut deferred_guard = self.deferred_processes.write().await;
+        let mut still_deferred = VecDeque::new();
+        while let Some(assigned_process) = deferred_guard.pop_front() {
+            if !self.has_free_slot(assigned_process.mode).await {
+                still_deferred.push_back(assigned_process);
+                continue;
+            }
+            let id = assigned_process.id.clone();
+            let result = self.launch(assigned_process).await;
+            if result.is_success() {
+                println!("Deferred process {:?} launched successfully", id);
+                continue;
+            }
+            println!(
+                "Failed to launch deferred child {:?}: {:?}",
+                id,
+                result.error_message()
+            );
+        }
+        *deferred_guard = still_deferred;
+    }
+
+    //checks the concurrency limit of the processing mode profile
+    async fn has_free_slot(&self, mode: ProcessingMode) -> bool {
+        let limit = match self.worker_profiles.for_mode(mode).max_children_count() {
+            Some(limit) => limit,
+            None => return true,
+        };
+        let processes_guard = self.processes.read().await;
+        let running_cnt = processes_guard
+            .values()
+            .filter(|process| process.mode() == mode)
+            .count();
+        running_cnt < limit
+    }
+
+    ///returns the captured output of a running or recently finished process
+    pub async fn get_logs(&self, id: &str) -> Option<Vec<LogLine>> {
+        self.log_buffers
+            .read()
+            .await
+            .get(id)
+            .map(|buffer| buffer.lines())
+    }
+
+    ///returns the captured output of a process and a receiver of its following lines
+    ///(None if the process output is already closed)
+    pub async fn follow_logs(
+        &self,
+        id: &str,
+    ) -> Option<(Vec<LogLine>, Option<broadcast::Receiver<LogLine>>)> {
+        self.log_buffers
+            .read()
+            .await
+            .get(id)
+            .map(|buffer| buffer.follow())
+    }
+
+    //starts tasks reading the child stdout/stderr into its log buffer
+    async fn capture_output(&self, assigned_process: &AssignedProcess, child: &mut Child) {
+        let id = assigned_process.id.as_str();
+        let forward_origin = match self.forward_child_output {
+            true => Some(OutputOrigin {
+                source_id: assigned_process.source_id,
+                supervisor_id: assigned_process.supervisor_id.clone(),
+            }),
+            false => None,
+        };
+        self.log_buffers
+            .write()
+            .await
+            .entry(id.to_string())
+            .or_insert_with(|| LogBuffer::new(self.log_buffer_lines))
+            .acquire();
+
+        if let Some(stdout) = child.stdout.take() {
+            process_logs::open_output(id, &self.log_buffers).await;
+            task::spawn(process_logs::capture_output(
+                stdout,
+                OutputStream::Stdout,
+                id.to_string(),
+                self.log_buffers.clone(),
+                forward_origin.clone(),
+            ));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            process_logs::open_output(id, &self.log_buffers).await;
+            task::spawn(process_logs::capture_output(
+                stderr,
+                OutputStream::Stderr,
+                id.to_string(),
+                self.log_buffers.clone(),
+                forward_origin,
+            ));
+        }
+    }
+
+    async fn release_logs(&self, id: &str) {
+        if let Some(buffer) = self.log_buffers.write().await.get_mut(id) {
+            buffer.release();
+        }
+    }
+
+    async fn purge_expired_logs(&self) {
+        let retention = Duration::from_secs(self.log_retention_secs);
+        self.log_buffers
+            .write()
+            .await
+            .retain(|_, buffer| !buffer.is_expired(retention));
+    }
+
+    //removes the context and payload files written on launch
+    async fn remove_launch_files(&self, id: &str) {
+        if let Some(dir) = &self.context_file_dir {
+            process_context::remove_context_file(dir, id).await;
+        }
+        if self.payload_policy.delivery == PayloadDelivery::File {
+            process_payload::remove_payload_file(&self.payload_policy.dir, id).await;
+        }
+    }
+
     pub async fn set_is_drain_mode(&self) {
         let mut is_drain_mode_guard = self.is_drain_mode.write().await;
         *is_drain_mode_guard = true;
@@ -632,12 +1793,20 @@ impl Supervisor {
     }
 
     pub async fn set_is_terminate_mode(&self) {
-        let mut is_terminate_mode_guard = self.is_terminate_mode.write().await;
-        *is_terminate_mode_guard = true;
+        self.is_terminate_mode.send_replace(true);
     }
     pub async fn is_terminate_mode(&self) -> bool {
-        let is_terminate_mode_guard = self.is_terminate_mode.read().await;
-        *is_terminate_mode_guard
+        *self.is_terminate_mode.borrow()
+    }
+
+    ///returns a receiver notified when the terminate mode is switched on
+    pub fn terminate_mode_receiver(&self) -> watch::Receiver<bool> {
+        self.is_terminate_mode.subscribe()
+    }
+
+    ///supervisor-wide pause, see pause_all()
+    pub async fn is_pause_mode(&self) -> bool {
+        *self.is_pause_mode.borrow()
     }
 }
 
@@ -646,11 +1815,29 @@ impl Clone for Supervisor {
         Self {
             dispatcher_client: self.dispatcher_client.clone(),
             processes: Arc::clone(&self.processes),
+            deferred_processes: Arc::clone(&self.deferred_processes),
+            pending_retries: Arc::clone(&self.pending_retries),
+            launching_ids: Arc::clone(&self.launching_ids),
             kill_queue: Arc::clone(&self.kill_queue),
+            kill_queue_changed: Arc::clone(&self.kill_queue_changed),
             is_drain_mode: Arc::clone(&self.is_drain_mode),
             is_terminate_mode: Arc::clone(&self.is_terminate_mode),
+            is_pause_mode: Arc::clone(&self.is_pause_mode),
+            signal_allowlist: self.signal_allowlist.clone(),
+            payload_policy: self.payload_policy.clone(),
+            workdir_policy: self.workdir_policy.clone(),
             max_children_count: self.max_children_count,
             sig_term_timeout: self.sig_term_timeout,
+            worker_profiles: self.worker_profiles.clone(),
+            context_file_dir: self.context_file_dir.clone(),
+            log_buffers: Arc::clone(&self.log_buffers),
+            log_buffer_lines: self.log_buffer_lines,
+            log_retention_secs: self.log_retention_secs,
+            forward_child_output: self.forward_child_output,
+            child_exited: Arc::clone(&self.child_exited),
+            cgroup_parent: self.cgroup_parent.clone(),
+            retry_policy: self.retry_policy.clone(),
+            crash_loop: Arc::clone(&self.crash_loop),
         }
     }
 }
@@ -659,29 +1846,101 @@ pub enum SlotsPopulationError {
     DrainModeObtained,
 }
 
-fn get_child_state(id: String, child: &mut Child) -> Result<ChildState, Error> {
-    let before_time = Instant::now();
-    let exit_status = child.try_wait()?;
-    println!(
-        "get_child_state: After child.try_wait()?, time: {:?}",
-        Instant::now().duration_since(before_time)
-    );
+fn get_child_state(id: String, process: &SupervisedProcess) -> ChildState {
+    let exit_status = process.exit_status();
     let is_finished = exit_status.is_some();
     let exit_code = exit_status.and_then(|status| status.code());
+    let signal = exit_status.and_then(|status| status.signal());
+    let core_dumped = exit_status.is_some_and(|status| status.core_dumped());
 
     #[cfg(not(target_os = "linux"))]
     let memory_kb = None;
     #[cfg(target_os = "linux")]
-    let memory_kb = get_memory_usage(child.id()).ok();
-
-    Ok(ChildState {
+    let memory_kb = match is_finished {
+        //the PID could be reused after the child is reaped
+        true => None,
+        false => get_memory_usage(process.pid).ok(),
+    };
+
+    let metrics = match is_finished {
+        true => None,
+        false => process_metrics::collect(process.pid),
+    };
+
+    let alive_descendant_pids = match is_finished {
+        true => process_group::alive_descendants(process.pid),
+        false => vec![],
+    };
+
+    ChildState {
         id,
+        pid: process.pid,
+        attempt: process.attempt,
+        state: process.state(),
+        transitions: process.lifecycle().transitions().to_vec(),
+        paused_secs: process.paused_duration().as_secs(),
         is_running: !is_finished,
         is_finished,
         exit_code,
-        is_killed: false,
+        signal,
+        core_dumped,
+        is_killed: process.lifecycle().has_been(LifecycleState::Killing),
         rss_anon_memory_kb: memory_kb,
-    })
+        metrics,
+        alive_descendant_pids,
+        cgroup_usage: process.cgroup.as_ref().map(|cgroup| cgroup.usage()),
+        termination_reason: process.termination_reason(),
+        events: process.events().to_vec(),
+    }
+}
+
+//writes the payload in the background, so a worker reading it slowly does not block the launch.
+//The stdin is closed afterwards
+fn write_stdin_payload(id: &str, child: &mut Child, bytes: Vec<u8>) {
+    let mut stdin = match child.stdin.take() {
+        Some(stdin) => stdin,
+        None => return,
+    };
+    let id = id.to_string();
+    task::spawn(async move {
+        if let Err(e) = stdin.write_all(&bytes).await {
+            //the worker does not have to read the whole payload
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                println!("Failed to write payload to process {}: {}", id, e);
+            }
+        }
+    });
+}
+
+fn pause_result(id: String, result: PauseResult) -> SelectedProcessResult {
+    match result.error_message() {
+        None => SelectedProcessResult::success(id, None),
+        Some(e) => SelectedProcessResult::error(id, e.clone()),
+    }
+}
+
+fn not_found_result(id: String) -> SelectedProcessResult {
+    let error = format!("Process {} is not supervised", id);
+    SelectedProcessResult::error(id, error)
+}
+
+fn finished_result(id: String) -> SelectedProcessResult {
+    let error = format!("Process {} is already finished", id);
+    SelectedProcessResult::error(id, error)
+}
+
+//the whole process group is counted, subprocesses forked by the worker would bypass the limit otherwise
+#[cfg(target_os = "linux")]
+fn sample_memory_kb(pgid: u32) -> Option<u64> {
+    process_group::members(pgid)
+        .into_iter()
+        .map(|pid| get_memory_usage(pid).ok())
+        .sum()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sample_memory_kb(_pgid: u32) -> Option<u64> {
+    None
 }
 
 ///returns size in kilobytes
@@ -707,5 +1966,5 @@ fn get_memory_usage(pid: u32) -> std::io::Result<u64> {
     }
 
     //not linux
-    return Ok(0);
+    Ok(0)
 }
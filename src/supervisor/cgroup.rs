@@ -0,0 +1,122 @@
+use super::process_id;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::time::{sleep, Duration};
+
+const CONTROLLERS: &str = "+memory +cpu +pids";
+
+///cgroup v2 limits, values are written to the cgroup files as is (e.g. memory_max "512M",
+///cpu_max "50000 100000", pids_max "64")
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CgroupLimits {
+    #[serde(default)]
+    pub memory_max: Option<String>,
+    #[serde(default)]
+    pub cpu_max: Option<String>,
+    #[serde(default)]
+    pub pids_max: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CgroupUsage {
+    memory_current_bytes: Option<u64>,
+    memory_peak_bytes: Option<u64>,
+    cpu_usage_usec: Option<u64>,
+    pids_current: Option<u64>,
+    oom_kill_count: Option<u64>,
+}
+
+///enables the controllers we set limits with for the children of the parent cgroup.
+///The parent must not contain processes itself (cgroup v2 "no internal processes" rule)
+pub fn prepare_parent(parent: &str) -> io::Result<()> {
+    fs::create_dir_all(parent)?;
+    fs::write(
+        Path::new(parent).join("cgroup.subtree_control"),
+        CONTROLLERS,
+    )
+}
+
+///a cgroup created for a single supervised process
+#[derive(Debug, Clone)]
+pub struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    ///creates (or reuses) the `{parent}/{id}` cgroup and applies the limits
+    pub fn create(parent: &str, id: &str, limits: &CgroupLimits) -> io::Result<Self> {
+        let path = Path::new(parent).join(process_id::path_component(id)?);
+        if !path.exists() {
+            fs::create_dir(&path)?;
+        }
+        let cgroup = Self { path };
+        cgroup.write("memory.max", limits.memory_max.as_deref())?;
+        cgroup.write("cpu.max", limits.cpu_max.as_deref())?;
+        cgroup.write("pids.max", limits.pids_max.as_deref())?;
+        Ok(cgroup)
+    }
+
+    ///opened before fork, so the child only has to write "0" (itself) into it before exec
+    pub fn open_procs(&self) -> io::Result<File> {
+        OpenOptions::new()
+            .write(true)
+            .open(self.path.join("cgroup.procs"))
+    }
+
+    pub fn usage(&self) -> CgroupUsage {
+        CgroupUsage {
+            memory_current_bytes: self.read_u64("memory.current"),
+            memory_peak_bytes: self.read_u64("memory.peak"),
+            cpu_usage_usec: self.read_keyed_u64("cpu.stat", "usage_usec"),
+            pids_current: self.read_u64("pids.current"),
+            oom_kill_count: self.read_keyed_u64("memory.events", "oom_kill"),
+        }
+    }
+
+    ///kills whatever is left in the cgroup and removes it
+    pub async fn remove(&self) {
+        let kill_file = self.path.join("cgroup.kill");
+        if tokio::fs::try_exists(&kill_file).await.unwrap_or(false) {
+            if let Err(e) = tokio::fs::write(&kill_file, "1").await {
+                println!("Failed to kill cgroup {:?} members: {}", self.path, e);
+            }
+        }
+        //killed processes leave the cgroup asynchronously, so the removal could fail for a while
+        for _ in 0..10 {
+            match tokio::fs::remove_dir(&self.path).await {
+                Ok(_) => return,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+                Err(_) => sleep(Duration::from_millis(100)).await,
+            }
+        }
+        println!("Failed to remove cgroup {:?}", self.path);
+    }
+
+    fn write(&self, file: &str, value: Option<&str>) -> io::Result<()> {
+        match value {
+            Some(value) => fs::write(self.path.join(file), value),
+            None => Ok(()),
+        }
+    }
+
+    fn read_u64(&self, file: &str) -> Option<u64> {
+        fs::read_to_string(self.path.join(file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    //reads a value from "key value" lines files like cpu.stat
+    fn read_keyed_u64(&self, file: &str, key: &str) -> Option<u64> {
+        fs::read_to_string(self.path.join(file))
+            .ok()?
+            .lines()
+            .find_map(|line| match line.split_once(' ') {
+                Some((k, value)) if k == key => value.trim().parse().ok(),
+                _ => None,
+            })
+    }
+}
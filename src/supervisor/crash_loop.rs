@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct CrashLoopPolicy {
+    ///how many failures within the window pause the slots population
+    pub max_failures: usize,
+    pub window: Duration,
+    ///a failed process which exited faster than this is counted as a failure
+    pub min_runtime: Duration,
+    ///doubled after every consecutive pause
+    pub initial_pause: Duration,
+    pub max_pause: Duration,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashLoopStatus {
+    is_paused: bool,
+    paused_until: Option<DateTime<Utc>>,
+    reason: Option<String>,
+    consecutive_pauses: u32,
+    recent_failures: usize,
+}
+
+///counts launch failures and quick failed exits, pauses the slots population once too many
+///of them happen within the window
+#[derive(Debug)]
+pub struct CrashLoopDetector {
+    policy: CrashLoopPolicy,
+    failures: VecDeque<Instant>,
+    paused_until: Option<(Instant, DateTime<Utc>)>,
+    reason: Option<String>,
+    consecutive_pauses: u32,
+}
+
+impl CrashLoopDetector {
+    pub fn new(policy: CrashLoopPolicy) -> Self {
+        Self {
+            policy,
+            failures: VecDeque::new(),
+            paused_until: None,
+            reason: None,
+            consecutive_pauses: 0,
+        }
+    }
+
+    pub fn min_runtime(&self) -> Duration {
+        self.policy.min_runtime
+    }
+
+    pub fn record_failure(&mut self, reason: String) {
+        let now = Instant::now();
+        self.failures.push_back(now);
+        while let Some(failed_at) = self.failures.front() {
+            if now.duration_since(*failed_at) <= self.policy.window {
+                break;
+            }
+            self.failures.pop_front();
+        }
+        if self.failures.len() < self.policy.max_failures {
+            return;
+        }
+
+        let exponent = self.consecutive_pauses.min(31);
+        let pause = self
+            .policy
+            .initial_pause
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.policy.max_pause);
+        println!(
+            "Crash loop detected: {} failures within {:?}, last one: {}. Pausing slots population for {:?}",
+            self.failures.len(),
+            self.policy.window,
+            reason,
+            pause
+        );
+        let paused_until = Utc::now() + chrono::Duration::from_std(pause).unwrap_or_default();
+        self.paused_until = Some((now + pause, paused_until));
+        self.reason = Some(reason);
+        self.consecutive_pauses += 1;
+        self.failures.clear();
+    }
+
+    ///a process ran long enough, so the worker is healthy again
+    pub fn record_healthy_run(&mut self) {
+        if self.consecutive_pauses > 0 {
+            println!("Worker looks healthy again, crash loop backoff is reset");
+        }
+        self.consecutive_pauses = 0;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_until
+            .is_some_and(|(until, _)| Instant::now() < until)
+    }
+
+    pub fn status(&self) -> CrashLoopStatus {
+        let is_paused = self.is_paused();
+        CrashLoopStatus {
+            is_paused,
+            paused_until: self
+                .paused_until
+                .filter(|_| is_paused)
+                .map(|(_, paused_until)| paused_until),
+            reason: self.reason.clone().filter(|_| is_paused),
+            consecutive_pauses: self.consecutive_pauses,
+            recent_failures: self.failures.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector(window: Duration) -> CrashLoopDetector {
+        CrashLoopDetector::new(CrashLoopPolicy {
+            max_failures: 3,
+            window,
+            min_runtime: Duration::from_secs(1),
+            initial_pause: Duration::from_secs(10),
+            max_pause: Duration::from_secs(25),
+        })
+    }
+
+    #[test]
+    fn pauses_after_max_failures_within_the_window() {
+        let mut detector = detector(Duration::from_secs(60));
+        detector.record_failure("first".to_owned());
+        detector.record_failure("second".to_owned());
+        assert!(!detector.is_paused());
+        assert_eq!(detector.status().recent_failures, 2);
+
+        detector.record_failure("third".to_owned());
+        assert!(detector.is_paused());
+        let status = detector.status();
+        assert_eq!(status.reason.as_deref(), Some("third"));
+        assert_eq!(status.consecutive_pauses, 1);
+        assert_eq!(status.recent_failures, 0);
+        assert!(status.paused_until.is_some());
+    }
+
+    #[test]
+    fn forgets_failures_outside_the_window() {
+        let mut detector = detector(Duration::from_millis(1));
+        detector.record_failure("first".to_owned());
+        detector.record_failure("second".to_owned());
+        std::thread::sleep(Duration::from_millis(10));
+        detector.record_failure("third".to_owned());
+        assert!(!detector.is_paused());
+        assert_eq!(detector.status().recent_failures, 1);
+    }
+
+    #[test]
+    fn pause_doubles_and_is_capped() {
+        let mut detector = detector(Duration::from_secs(60));
+        let mut pauses = vec![];
+        for _ in 0..3 {
+            let before = Instant::now();
+            for _ in 0..3 {
+                detector.record_failure("failed".to_owned());
+            }
+            let (until, _) = detector.paused_until.unwrap();
+            pauses.push((until - before).as_secs());
+        }
+        assert_eq!(pauses, vec![10, 20, 25]);
+    }
+
+    #[test]
+    fn healthy_run_resets_consecutive_pauses() {
+        let mut detector = detector(Duration::from_secs(60));
+        for _ in 0..3 {
+            detector.record_failure("failed".to_owned());
+        }
+        assert_eq!(detector.status().consecutive_pauses, 1);
+        detector.record_healthy_run();
+        assert_eq!(detector.status().consecutive_pauses, 0);
+    }
+
+    #[test]
+    fn status_hides_the_reason_when_not_paused() {
+        let detector = detector(Duration::from_secs(60));
+        let status = detector.status();
+        assert!(!status.is_paused);
+        assert!(status.reason.is_none());
+        assert!(status.paused_until.is_none());
+    }
+}
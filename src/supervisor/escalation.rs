@@ -0,0 +1,208 @@
+use nix::sys::signal::Signal;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscalationStep {
+    pub signal: Signal,
+    ///how long to wait for the exit before the next step, None only for the final SIGKILL
+    pub wait: Option<Duration>,
+}
+
+///ordered signals sent to a process group being terminated, e.g. "SIGINT:5,SIGTERM:20,SIGKILL".
+///Always ends with SIGKILL, it is appended if the configured chain ends with another signal
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct EscalationChain {
+    //never empty
+    steps: Vec<EscalationStep>,
+}
+
+impl EscalationChain {
+    ///the classic SIGTERM, then SIGKILL after the timeout
+    pub fn sigterm_then_sigkill(sig_term_timeout: Duration) -> Self {
+        Self {
+            steps: vec![
+                EscalationStep {
+                    signal: Signal::SIGTERM,
+                    wait: Some(sig_term_timeout),
+                },
+                EscalationStep {
+                    signal: Signal::SIGKILL,
+                    wait: None,
+                },
+            ],
+        }
+    }
+
+    pub fn steps(&self) -> &[EscalationStep] {
+        &self.steps
+    }
+
+    pub fn first(&self) -> &EscalationStep {
+        &self.steps[0]
+    }
+
+    ///time since the first signal at which the given step is due
+    pub fn offset(&self, step: usize) -> Duration {
+        self.steps[..step].iter().filter_map(|step| step.wait).sum()
+    }
+
+    ///time since the first signal at which SIGKILL is sent
+    pub fn total_wait(&self) -> Duration {
+        self.offset(self.steps.len() - 1)
+    }
+}
+
+impl FromStr for EscalationChain {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut steps = vec![];
+        for step in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if steps
+                .last()
+                .is_some_and(|last: &EscalationStep| last.signal == Signal::SIGKILL)
+            {
+                return Err(format!("step {} follows SIGKILL", step));
+            }
+            let (name, wait) = match step.split_once(':') {
+                Some((name, secs)) => {
+                    let secs = secs
+                        .trim()
+                        .parse::<u64>()
+                        .map_err(|e| format!("invalid wait of step {}: {}", step, e))?;
+                    (name.trim(), Some(Duration::from_secs(secs)))
+                }
+                None => (step, None),
+            };
+            let signal = parse_signal(name)?;
+            if signal != Signal::SIGKILL && wait.is_none() {
+                return Err(format!(
+                    "step {} has no wait, only SIGKILL may omit it",
+                    step
+                ));
+            }
+            steps.push(EscalationStep {
+                signal,
+                //nothing is left to wait for after SIGKILL
+                wait: wait.filter(|_| signal != Signal::SIGKILL),
+            });
+        }
+        match steps.last() {
+            None => return Err("the chain is empty".to_owned()),
+            Some(last) if last.signal != Signal::SIGKILL => steps.push(EscalationStep {
+                signal: Signal::SIGKILL,
+                wait: None,
+            }),
+            _ => {}
+        }
+        Ok(Self { steps })
+    }
+}
+
+impl TryFrom<String> for EscalationChain {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for EscalationChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| match step.wait {
+                Some(wait) => format!("{}:{}", step.signal, wait.as_secs()),
+                None => step.signal.to_string(),
+            })
+            .collect();
+        write!(f, "{}", steps.join(","))
+    }
+}
+
+///accepts both "SIGTERM" and "TERM"
+pub fn parse_signal(name: &str) -> Result<Signal, String> {
+    let name = name.trim().to_uppercase();
+    let name = match name.starts_with("SIG") {
+        true => name,
+        false => format!("SIG{}", name),
+    };
+    Signal::from_str(&name).map_err(|_| format!("unknown signal {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Option<Duration> {
+        Some(Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn parses_a_chain() {
+        let chain: EscalationChain = "SIGINT:5, TERM:20 ,SIGKILL".parse().unwrap();
+        let steps: Vec<(Signal, Option<Duration>)> = chain
+            .steps()
+            .iter()
+            .map(|step| (step.signal, step.wait))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (Signal::SIGINT, secs(5)),
+                (Signal::SIGTERM, secs(20)),
+                (Signal::SIGKILL, None),
+            ]
+        );
+        assert_eq!(chain.offset(1), Duration::from_secs(5));
+        assert_eq!(chain.total_wait(), Duration::from_secs(25));
+        assert_eq!(chain.to_string(), "SIGINT:5,SIGTERM:20,SIGKILL");
+    }
+
+    #[test]
+    fn appends_sigkill() {
+        let chain: EscalationChain = "SIGTERM:10".parse().unwrap();
+        assert_eq!(
+            chain,
+            EscalationChain::sigterm_then_sigkill(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn ignores_a_wait_after_sigkill() {
+        let chain: EscalationChain = "SIGKILL:3".parse().unwrap();
+        assert_eq!(chain.steps().len(), 1);
+        assert_eq!(chain.first().wait, None);
+        assert_eq!(chain.total_wait(), Duration::ZERO);
+    }
+
+    #[test]
+    fn rejects_invalid_chains() {
+        for chain in [
+            "",
+            " , ",
+            "SIGTERM",
+            "SIGTERM:soon",
+            "SIGNOPE:5",
+            "SIGKILL,SIGTERM:5",
+        ] {
+            assert!(
+                chain.parse::<EscalationChain>().is_err(),
+                "{:?} must be rejected",
+                chain
+            );
+        }
+    }
+
+    #[test]
+    fn parses_signal_names_with_and_without_prefix() {
+        assert_eq!(parse_signal("hup").unwrap(), Signal::SIGHUP);
+        assert_eq!(parse_signal(" SIGUSR1 ").unwrap(), Signal::SIGUSR1);
+        assert!(parse_signal("SIGFOO").is_err());
+    }
+}
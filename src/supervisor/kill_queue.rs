@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use tokio::time::Instant;
+
+///an escalation chain step of a process, the first signal is sent before scheduling
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledStep {
+    pub id: String,
+    ///the step belongs to this launch only, the id could be relaunched meanwhile
+    pub pid: u32,
+    pub step: usize,
+}
+
+///pending escalation steps ordered by their deadlines
+#[derive(Debug, Default)]
+pub struct KillQueue {
+    //the sequence keeps steps with the same deadline in the scheduling order
+    steps: BTreeMap<(Instant, u64), ScheduledStep>,
+    sequence: u64,
+}
+
+impl KillQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn schedule(&mut self, deadline: Instant, step: ScheduledStep) {
+        self.sequence += 1;
+        self.steps.insert((deadline, self.sequence), step);
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.steps.keys().next().map(|(deadline, _)| *deadline)
+    }
+
+    ///removes and returns all steps due at the given time, the earliest first
+    pub fn pop_due(&mut self, now: Instant) -> Vec<ScheduledStep> {
+        let not_due = self.steps.split_off(&(now, u64::MAX));
+        std::mem::replace(&mut self.steps, not_due)
+            .into_values()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn step(id: &str, step: usize) -> ScheduledStep {
+        ScheduledStep {
+            id: id.to_owned(),
+            pid: 1,
+            step,
+        }
+    }
+
+    #[test]
+    fn pops_due_steps_in_deadline_order() {
+        let now = Instant::now();
+        let mut queue = KillQueue::new();
+        queue.schedule(now + Duration::from_secs(3), step("late", 1));
+        queue.schedule(now + Duration::from_secs(1), step("early", 1));
+        queue.schedule(now + Duration::from_secs(2), step("middle", 1));
+        queue.schedule(now + Duration::from_secs(10), step("not due", 1));
+
+        assert_eq!(queue.next_deadline(), Some(now + Duration::from_secs(1)));
+        let due = queue.pop_due(now + Duration::from_secs(3));
+        let ids: Vec<&str> = due.iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(ids, vec!["early", "middle", "late"]);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.next_deadline(), Some(now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn keeps_the_scheduling_order_of_equal_deadlines() {
+        let deadline = Instant::now();
+        let mut queue = KillQueue::new();
+        for i in 0..5 {
+            queue.schedule(deadline, step("same", i));
+        }
+        let steps: Vec<usize> = queue.pop_due(deadline).iter().map(|s| s.step).collect();
+        assert_eq!(steps, vec![0, 1, 2, 3, 4]);
+        assert_eq!(queue.len(), 0);
+        assert_eq!(queue.next_deadline(), None);
+    }
+
+    #[test]
+    fn pops_nothing_before_the_first_deadline() {
+        let now = Instant::now();
+        let mut queue = KillQueue::new();
+        queue.schedule(now + Duration::from_secs(1), step("later", 1));
+        assert!(queue.pop_due(now).is_empty());
+        assert_eq!(queue.len(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleState {
+    Launching,
+    Running,
+    //SIGSTOP is sent, the process group is stopped until resumed with SIGCONT
+    Paused,
+    //a graceful signal of the escalation chain is sent, waiting for the process to exit
+    Terminating,
+    //SIGKILL is sent
+    Killing,
+    Exited,
+    //the finish report is being sent to the dispatcher
+    Reporting,
+    Reported,
+}
+
+impl LifecycleState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleState::Launching => "launching",
+            LifecycleState::Running => "running",
+            LifecycleState::Paused => "paused",
+            LifecycleState::Terminating => "terminating",
+            LifecycleState::Killing => "killing",
+            LifecycleState::Exited => "exited",
+            LifecycleState::Reporting => "reporting",
+            LifecycleState::Reported => "reported",
+        }
+    }
+
+    fn can_transition_to(&self, to: LifecycleState) -> bool {
+        use LifecycleState::*;
+        matches!(
+            (self, to),
+            (Launching, Running)
+                | (Launching, Exited)
+                | (Running, Terminating)
+                | (Running, Killing)
+                | (Running, Exited)
+                | (Running, Paused)
+                | (Paused, Running)
+                | (Paused, Terminating)
+                | (Paused, Killing)
+                | (Paused, Exited)
+                | (Terminating, Killing)
+                | (Terminating, Exited)
+                | (Killing, Exited)
+                | (Exited, Reporting)
+                | (Reporting, Reported)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transition {
+    state: LifecycleState,
+    at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct IllegalTransition {
+    from: LifecycleState,
+    to: LifecycleState,
+}
+
+impl fmt::Display for IllegalTransition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "transition from {} to {} is not allowed",
+            self.from.as_str(),
+            self.to.as_str()
+        )
+    }
+}
+
+///the state of a supervised process with the history of how it got there
+#[derive(Debug, Clone)]
+pub struct Lifecycle {
+    //never empty, the last one is the current state
+    transitions: Vec<Transition>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            transitions: vec![Transition {
+                state: LifecycleState::Launching,
+                at: Utc::now(),
+            }],
+        }
+    }
+
+    pub fn state(&self) -> LifecycleState {
+        self.transitions
+            .last()
+            .map(|transition| transition.state)
+            .unwrap_or(LifecycleState::Launching)
+    }
+
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    pub fn has_been(&self, state: LifecycleState) -> bool {
+        self.entered_at(state).is_some()
+    }
+
+    pub fn entered_at(&self, state: LifecycleState) -> Option<DateTime<Utc>> {
+        self.transitions
+            .iter()
+            .find(|transition| transition.state == state)
+            .map(|transition| transition.at)
+    }
+
+    ///checks the transition without making it
+    pub fn check_transition(&self, to: LifecycleState) -> Result<(), IllegalTransition> {
+        let from = self.state();
+        match from.can_transition_to(to) {
+            true => Ok(()),
+            false => Err(IllegalTransition { from, to }),
+        }
+    }
+
+    pub fn transition(&mut self, to: LifecycleState) -> Result<(), IllegalTransition> {
+        self.check_transition(to)?;
+        self.transitions.push(Transition {
+            state: to,
+            at: Utc::now(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LifecycleState::*;
+    use super::*;
+
+    #[test]
+    fn follows_a_regular_run() {
+        let mut lifecycle = Lifecycle::new();
+        assert_eq!(lifecycle.state(), Launching);
+        for state in [Running, Exited, Reporting, Reported] {
+            lifecycle.transition(state).unwrap();
+            assert_eq!(lifecycle.state(), state);
+        }
+        assert_eq!(lifecycle.transitions().len(), 5);
+        assert!(lifecycle.has_been(Running));
+        assert!(!lifecycle.has_been(Terminating));
+    }
+
+    #[test]
+    fn follows_an_escalated_termination_of_a_paused_process() {
+        let mut lifecycle = Lifecycle::new();
+        for state in [
+            Running,
+            Paused,
+            Running,
+            Paused,
+            Terminating,
+            Killing,
+            Exited,
+        ] {
+            lifecycle.transition(state).unwrap();
+        }
+        assert!(lifecycle.has_been(Killing));
+        assert!(lifecycle.entered_at(Paused) <= lifecycle.entered_at(Exited));
+    }
+
+    #[test]
+    fn rejects_illegal_transitions() {
+        let mut lifecycle = Lifecycle::new();
+        assert!(lifecycle.transition(Reporting).is_err());
+        assert!(lifecycle.transition(Paused).is_err());
+        lifecycle.transition(Running).unwrap();
+        lifecycle.transition(Killing).unwrap();
+        assert!(lifecycle.transition(Terminating).is_err());
+        assert!(lifecycle.transition(Running).is_err());
+        lifecycle.transition(Exited).unwrap();
+        assert!(lifecycle.transition(Running).is_err());
+        assert!(lifecycle.transition(Reported).is_err());
+        //a rejected transition is not recorded
+        assert_eq!(lifecycle.state(), Exited);
+        assert_eq!(lifecycle.transitions().len(), 4);
+    }
+
+    #[test]
+    fn checks_transitions_without_making_them() {
+        let lifecycle = Lifecycle::new();
+        assert!(lifecycle.check_transition(Running).is_ok());
+        assert!(lifecycle.check_transition(Paused).is_err());
+        assert_eq!(lifecycle.state(), Launching);
+    }
+
+    #[test]
+    fn reports_the_illegal_transition() {
+        let mut lifecycle = Lifecycle::new();
+        let error = lifecycle.transition(Reported).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "transition from launching to reported is not allowed"
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use super::process_id;
+use crate::dispatcher::{AssignedProcess, DispatchState, ProcessingMode};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+//environment variables every worker gets from the supervisor
+pub const ENV_PROCESS_ID: &str = "PS_PROCESS_ID";
+pub const ENV_SOURCE_ID: &str = "PS_SOURCE_ID";
+pub const ENV_PROCESSING_MODE: &str = "PS_PROCESSING_MODE";
+//RFC 3339 formatted
+pub const ENV_CREATED_AT: &str = "PS_CREATED_AT";
+pub const ENV_SUPERVISOR_ID: &str = "PS_SUPERVISOR_ID";
+//set only if the context file directory is configured
+pub const ENV_CONTEXT_FILE: &str = "PS_CONTEXT_FILE";
+
+//the assigned process without the payload, the worker gets the payload separately
+#[derive(Serialize)]
+struct ProcessContext<'a> {
+    id: &'a str,
+    source_id: u32,
+    state: &'a DispatchState,
+    mode: ProcessingMode,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    created_at: DateTime<Utc>,
+    supervisor_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_runtime_secs: Option<u64>,
+}
+
+impl<'a> From<&'a AssignedProcess> for ProcessContext<'a> {
+    fn from(assigned_process: &'a AssignedProcess) -> Self {
+        Self {
+            id: &assigned_process.id,
+            source_id: assigned_process.source_id,
+            state: &assigned_process.state,
+            mode: assigned_process.mode,
+            created_at: assigned_process.created_at,
+            supervisor_id: &assigned_process.supervisor_id,
+            max_runtime_secs: assigned_process.max_runtime_secs,
+        }
+    }
+}
+
+///returns the assigned process context as a list of environment variables for the child
+pub fn context_env(assigned_process: &AssignedProcess) -> Vec<(&'static str, String)> {
+    vec![
+        (ENV_PROCESS_ID, assigned_process.id.clone()),
+        (ENV_SOURCE_ID, assigned_process.source_id.to_string()),
+        (
+            ENV_PROCESSING_MODE,
+            assigned_process.mode.as_str().to_string(),
+        ),
+        (ENV_CREATED_AT, assigned_process.created_at.to_rfc3339()),
+        (ENV_SUPERVISOR_ID, assigned_process.supervisor_id.clone()),
+    ]
+}
+
+pub fn context_file_path(dir: &str, id: &str) -> std::io::Result<PathBuf> {
+    let id = process_id::path_component(id)?;
+    Ok(Path::new(dir).join(format!("{}.json", id)))
+}
+
+///writes the assigned process as JSON (the same format the dispatcher responds with, except
+///the payload) and returns the file path
+pub async fn write_context_file(
+    dir: &str,
+    assigned_process: &AssignedProcess,
+) -> std::io::Result<PathBuf> {
+    let path = context_file_path(dir, &assigned_process.id)?;
+    let json = serde_json::to_vec(&ProcessContext::from(assigned_process))?;
+    tokio::fs::create_dir_all(dir).await?;
+    //only the worker should read it
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&path)
+        .await?;
+    file.write_all(&json).await?;
+    file.flush().await?;
+    Ok(path)
+}
+
+pub async fn remove_context_file(dir: &str, id: &str) {
+    let path = match context_file_path(dir, id) {
+        Ok(path) => path,
+        //nothing could be written for such an id
+        Err(_) => return,
+    };
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            println!("Failed to remove context file {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_leaves_the_payload_out() {
+        let mut assigned_process = AssignedProcess::manual("42".to_owned(), "sv1".to_owned());
+        assigned_process.payload = Some(serde_json::json!({"secret": "value"}));
+        let json = serde_json::to_value(ProcessContext::from(&assigned_process)).unwrap();
+
+        assert_eq!(json["id"], "42");
+        assert_eq!(json["mode"], "Regular");
+        assert_eq!(json["supervisor_id"], "sv1");
+        assert!(json.get("payload").is_none());
+        assert!(json.get("max_runtime_secs").is_none());
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessEventKind {
+    MemorySoftLimitExceeded,
+    MemoryHardLimitExceeded,
+    DeadlineReached,
+    //delivered via the HTTP API
+    SignalSent,
+}
+
+///something notable which happened to a supervised process, kept until the process is removed
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessEvent {
+    at: DateTime<Utc>,
+    kind: ProcessEventKind,
+    message: String,
+}
+
+impl ProcessEvent {
+    pub fn new(kind: ProcessEventKind, message: String) -> Self {
+        Self {
+            at: Utc::now(),
+            kind,
+            message,
+        }
+    }
+
+    pub fn kind(&self) -> ProcessEventKind {
+        self.kind
+    }
+}
@@ -0,0 +1,39 @@
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+
+///every worker is launched as a leader of its own process group (PGID equals the worker PID),
+///so signals reach the subprocesses it forked as well
+pub fn signal_group(pgid: u32, signal: Signal) -> nix::Result<()> {
+    signal::killpg(Pid::from_raw(pgid as i32), signal)
+}
+
+///returns PIDs of the group members except the leader itself
+pub fn alive_descendants(pgid: u32) -> Vec<u32> {
+    members(pgid)
+        .into_iter()
+        .filter(|pid| *pid != pgid)
+        .collect()
+}
+
+///returns PIDs of all live group members, the leader included.
+///Zombies are skipped, they hold no resources and ignore signals
+#[cfg(target_os = "linux")]
+pub fn members(pgid: u32) -> Vec<u32> {
+    let processes = match procfs::process::all_processes() {
+        Ok(processes) => processes,
+        Err(e) => {
+            println!("Unable to list processes: {}", e);
+            return vec![];
+        }
+    };
+    processes
+        .filter_map(|process| process.ok()?.stat().ok())
+        .filter(|stat| stat.pgrp == pgid as i32 && stat.state != 'Z')
+        .map(|stat| stat.pid as u32)
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn members(_pgid: u32) -> Vec<u32> {
+    vec![]
+}
@@ -0,0 +1,47 @@
+use std::io;
+
+///process ids come from the dispatcher or the HTTP API and become file, directory and cgroup
+///names, so an id must be a single harmless path component
+pub fn validate(id: &str) -> Result<(), String> {
+    if id.is_empty() {
+        return Err("process id is empty".to_owned());
+    }
+    if id.contains('/') || id.contains('\0') {
+        return Err(format!("process id {:?} contains a path separator", id));
+    }
+    if id.contains("..") || id == "." {
+        return Err(format!("process id {:?} refers to a directory", id));
+    }
+    Ok(())
+}
+
+///validate() for the path builders, so no file operation escapes its directory
+pub fn path_component(id: &str) -> io::Result<&str> {
+    validate(id).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_ids() {
+        assert!(validate("42").is_ok());
+        assert!(validate("job-7_retry.v2").is_ok());
+    }
+
+    #[test]
+    fn rejects_ids_escaping_the_directory() {
+        for id in ["", ".", "..", "../etc", "a/../b", "/tmp", "a/b", "a\0b"] {
+            assert!(validate(id).is_err(), "{:?} must be rejected", id);
+        }
+    }
+
+    #[test]
+    fn path_component_returns_an_invalid_input_error() {
+        assert_eq!(path_component("7").unwrap(), "7");
+        let error = path_component("../7").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,313 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::{broadcast, RwLock};
+use tokio::time::{Duration, Instant};
+
+//longer lines are truncated, so a single line can't blow up the buffer
+const MAX_LINE_BYTES: usize = 16 * 1024;
+//how many lines a slow follower may fall behind before it starts skipping them
+const FOLLOW_CHANNEL_CAPACITY: usize = 1024;
+
+pub type LogBuffers = Arc<RwLock<HashMap<String, LogBuffer>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    stream: OutputStream,
+    timestamp: DateTime<Utc>,
+    line: String,
+}
+
+///identifies the process in the output lines forwarded to the supervisor stdout
+#[derive(Debug, Clone)]
+pub struct OutputOrigin {
+    pub source_id: u32,
+    pub supervisor_id: String,
+}
+
+//a child output line re-emitted as JSON, so log shippers can index it per process
+#[derive(Serialize)]
+struct ForwardedLine<'a> {
+    process_id: &'a str,
+    source_id: u32,
+    supervisor_id: &'a str,
+    stream: OutputStream,
+    timestamp: DateTime<Utc>,
+    message: &'a str,
+}
+
+///keeps the last `capacity` output lines of a process
+#[derive(Debug)]
+pub struct LogBuffer {
+    lines: VecDeque<LogLine>,
+    capacity: usize,
+    //live lines for followers, dropped once every output stream of the process is closed
+    sender: Option<broadcast::Sender<LogLine>>,
+    open_streams: usize,
+    //set when the process is removed from the supervisor, the buffer is dropped after the retention
+    released_at: Option<Instant>,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity.min(1024)),
+            capacity,
+            sender: None,
+            open_streams: 0,
+            released_at: None,
+        }
+    }
+
+    pub fn push(&mut self, line: LogLine) {
+        if let Some(sender) = &self.sender {
+            //no followers is not an error
+            let _ = sender.send(line.clone());
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        if self.lines.len() >= self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn lines(&self) -> Vec<LogLine> {
+        self.lines.iter().cloned().collect()
+    }
+
+    ///returns the buffered lines and a receiver of the following ones.
+    ///The receiver is None if the process output is already closed
+    pub fn follow(&self) -> (Vec<LogLine>, Option<broadcast::Receiver<LogLine>>) {
+        (
+            self.lines(),
+            self.sender.as_ref().map(|sender| sender.subscribe()),
+        )
+    }
+
+    fn open_stream(&mut self) {
+        if self.sender.is_none() {
+            self.sender = Some(broadcast::channel(FOLLOW_CHANNEL_CAPACITY).0);
+        }
+        self.open_streams += 1;
+    }
+
+    fn close_stream(&mut self) {
+        self.open_streams = self.open_streams.saturating_sub(1);
+        if self.open_streams == 0 {
+            //followers get RecvError::Closed
+            self.sender = None;
+        }
+    }
+
+    ///the process id is in use again (e.g. relaunched), keep the buffer
+    pub fn acquire(&mut self) {
+        self.released_at = None;
+    }
+
+    pub fn release(&mut self) {
+        self.released_at = Some(Instant::now());
+    }
+
+    pub fn is_expired(&self, retention: Duration) -> bool {
+        self.released_at
+            .is_some_and(|released_at| released_at.elapsed() >= retention)
+    }
+}
+
+///registers the child output stream in the process log buffer, must be called before
+///the capture_output() task is started
+pub async fn open_output(id: &str, log_buffers: &LogBuffers) {
+    if let Some(buffer) = log_buffers.write().await.get_mut(id) {
+        buffer.open_stream();
+    }
+}
+
+///reads the child output line by line into the process log buffer until EOF.
+///If the origin is set, every line is also printed to the supervisor stdout as JSON
+pub async fn capture_output<R: AsyncRead + Unpin>(
+    reader: R,
+    stream: OutputStream,
+    id: String,
+    log_buffers: LogBuffers,
+    forward_origin: Option<OutputOrigin>,
+) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match read_line_bounded(&mut reader, &mut buf, MAX_LINE_BYTES).await {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                println!("Failed to read {:?} of process {}: {}", stream, id, e);
+                break;
+            }
+        }
+        let line = String::from_utf8_lossy(&buf)
+            .trim_end_matches(['\n', '\r'])
+            .to_string();
+        let log_line = LogLine {
+            stream,
+            timestamp: Utc::now(),
+            line,
+        };
+        if let Some(origin) = &forward_origin {
+            forward_line(&id, origin, &log_line);
+        }
+        if let Some(buffer) = log_buffers.write().await.get_mut(&id) {
+            buffer.push(log_line);
+        }
+    }
+    if let Some(buffer) = log_buffers.write().await.get_mut(&id) {
+        buffer.close_stream();
+    }
+}
+
+//reads a line like read_until(), but keeps at most max_bytes of it. The rest of a longer line
+//is discarded as it streams, so a child writing without newlines can't exhaust the memory.
+//Returns the number of consumed bytes, 0 at the end of the stream
+async fn read_line_bounded<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    max_bytes: usize,
+) -> std::io::Result<usize> {
+    let mut consumed = 0;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(consumed);
+        }
+        let (chunk, is_line_end) = match available.iter().position(|byte| *byte == b'\n') {
+            Some(end) => (&available[..=end], true),
+            None => (available, false),
+        };
+        let kept = chunk.len().min(max_bytes.saturating_sub(buf.len()));
+        buf.extend_from_slice(&chunk[..kept]);
+        let chunk_len = chunk.len();
+        reader.consume(chunk_len);
+        consumed += chunk_len;
+        if is_line_end {
+            return Ok(consumed);
+        }
+    }
+}
+
+fn forward_line(id: &str, origin: &OutputOrigin, log_line: &LogLine) {
+    let forwarded = ForwardedLine {
+        process_id: id,
+        source_id: origin.source_id,
+        supervisor_id: &origin.supervisor_id,
+        stream: log_line.stream,
+        timestamp: log_line.timestamp,
+        message: &log_line.line,
+    };
+    match serde_json::to_string(&forwarded) {
+        Ok(json) => println!("{}", json),
+        Err(e) => println!("Failed to serialize output line of process {}: {}", id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::error::RecvError;
+
+    fn log_line(line: &str) -> LogLine {
+        LogLine {
+            stream: OutputStream::Stdout,
+            timestamp: Utc::now(),
+            line: line.to_owned(),
+        }
+    }
+
+    fn texts(buffer: &LogBuffer) -> Vec<String> {
+        buffer.lines().into_iter().map(|line| line.line).collect()
+    }
+
+    #[test]
+    fn buffer_keeps_the_last_lines() {
+        let mut buffer = LogBuffer::new(2);
+        buffer.push(log_line("a"));
+        buffer.push(log_line("b"));
+        buffer.push(log_line("c"));
+        assert_eq!(texts(&buffer), vec!["b", "c"]);
+
+        let mut buffer = LogBuffer::new(0);
+        buffer.push(log_line("a"));
+        assert!(buffer.lines().is_empty());
+    }
+
+    #[test]
+    fn buffer_expires_only_after_release() {
+        let mut buffer = LogBuffer::new(2);
+        assert!(!buffer.is_expired(Duration::ZERO));
+
+        buffer.release();
+        assert!(buffer.is_expired(Duration::ZERO));
+        assert!(!buffer.is_expired(Duration::from_secs(3600)));
+
+        buffer.acquire();
+        assert!(!buffer.is_expired(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn followers_get_closed_with_the_last_stream() {
+        let mut buffer = LogBuffer::new(2);
+        assert!(buffer.follow().1.is_none());
+
+        buffer.open_stream();
+        buffer.open_stream();
+        let (_, receiver) = buffer.follow();
+        let mut receiver = receiver.unwrap();
+        buffer.push(log_line("a"));
+        assert_eq!(receiver.recv().await.unwrap().line, "a");
+
+        buffer.close_stream();
+        assert!(buffer.follow().1.is_some());
+        buffer.close_stream();
+        assert!(matches!(receiver.recv().await, Err(RecvError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn read_line_bounded_truncates_long_lines() {
+        let mut reader = BufReader::with_capacity(4, "abcdefghij\nxy\nz".as_bytes());
+        let mut buf = Vec::new();
+
+        assert_eq!(
+            read_line_bounded(&mut reader, &mut buf, 5).await.unwrap(),
+            11
+        );
+        assert_eq!(buf, b"abcde");
+
+        buf.clear();
+        assert_eq!(
+            read_line_bounded(&mut reader, &mut buf, 5).await.unwrap(),
+            3
+        );
+        assert_eq!(buf, b"xy\n");
+
+        buf.clear();
+        assert_eq!(
+            read_line_bounded(&mut reader, &mut buf, 5).await.unwrap(),
+            1
+        );
+        assert_eq!(buf, b"z");
+
+        buf.clear();
+        assert_eq!(
+            read_line_bounded(&mut reader, &mut buf, 5).await.unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+///resource usage of a running worker (the group leader only) read from procfs
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProcessMetrics {
+    started_at: Option<DateTime<Utc>>,
+    uptime_secs: Option<i64>,
+    cpu_user_ms: Option<u64>,
+    cpu_system_ms: Option<u64>,
+    threads: Option<i64>,
+    open_fds: Option<usize>,
+    //bytes fetched from / sent to the storage layer
+    read_bytes: Option<u64>,
+    write_bytes: Option<u64>,
+    peak_rss_kb: Option<u64>,
+}
+
+///every metric is optional: e.g. io counters are not readable without ptrace permissions.
+///Returns None if the process is gone
+#[cfg(target_os = "linux")]
+pub fn collect(pid: u32) -> Option<ProcessMetrics> {
+    use procfs::WithCurrentSystemInfo;
+
+    let process = procfs::process::Process::new(pid as i32).ok()?;
+    let stat = process.stat().ok()?;
+    let ticks_per_second = procfs::ticks_per_second();
+    let ticks_to_ms = |ticks: u64| ticks * 1000 / ticks_per_second;
+
+    let started_at = stat
+        .starttime()
+        .get()
+        .ok()
+        .map(|started_at| started_at.with_timezone(&Utc));
+    let io = process.io().ok();
+
+    Some(ProcessMetrics {
+        started_at,
+        uptime_secs: started_at.map(|started_at| (Utc::now() - started_at).num_seconds()),
+        cpu_user_ms: Some(ticks_to_ms(stat.utime)),
+        cpu_system_ms: Some(ticks_to_ms(stat.stime)),
+        threads: Some(stat.num_threads),
+        open_fds: process.fd_count().ok(),
+        read_bytes: io.as_ref().map(|io| io.read_bytes),
+        write_bytes: io.as_ref().map(|io| io.write_bytes),
+        peak_rss_kb: process.status().ok().and_then(|status| status.vmhwm),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn collect(_pid: u32) -> Option<ProcessMetrics> {
+    None
+}
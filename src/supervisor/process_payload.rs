@@ -0,0 +1,85 @@
+use super::process_id;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::io::AsyncWriteExt;
+
+//set only if the payload is delivered via a file
+pub const ENV_PAYLOAD_FILE: &str = "PS_PAYLOAD_FILE";
+
+///how the dispatcher payload reaches the worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadDelivery {
+    ///written to the child stdin, which is closed afterwards
+    Stdin,
+    ///written to a file readable only by the supervisor user, its path is in PS_PAYLOAD_FILE
+    File,
+}
+
+impl FromStr for PayloadDelivery {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "stdin" => Ok(PayloadDelivery::Stdin),
+            "file" => Ok(PayloadDelivery::File),
+            _ => Err(format!(
+                "unknown payload delivery {}, expected stdin or file",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PayloadPolicy {
+    pub delivery: PayloadDelivery,
+    ///payload files directory, used only by the file delivery
+    pub dir: String,
+    ///the launch fails if the payload is larger
+    pub max_bytes: usize,
+}
+
+///JSON strings are passed as they are, so opaque data (e.g. base64) needs no decoding
+///of JSON quotes and escapes. Any other value is passed as JSON
+pub fn payload_bytes(payload: &Value) -> Vec<u8> {
+    match payload {
+        Value::String(value) => value.as_bytes().to_vec(),
+        _ => payload.to_string().into_bytes(),
+    }
+}
+
+pub fn payload_file_path(dir: &str, id: &str) -> std::io::Result<PathBuf> {
+    let id = process_id::path_component(id)?;
+    Ok(Path::new(dir).join(format!("{}.payload", id)))
+}
+
+///writes the payload and returns the file path
+pub async fn write_payload_file(dir: &str, id: &str, bytes: &[u8]) -> std::io::Result<PathBuf> {
+    let path = payload_file_path(dir, id)?;
+    tokio::fs::create_dir_all(dir).await?;
+    //job input may be sensitive
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&path)
+        .await?;
+    file.write_all(bytes).await?;
+    file.flush().await?;
+    Ok(path)
+}
+
+pub async fn remove_payload_file(dir: &str, id: &str) {
+    let path = match payload_file_path(dir, id) {
+        Ok(path) => path,
+        //nothing could be written for such an id
+        Err(_) => return,
+    };
+    if let Err(e) = tokio::fs::remove_file(&path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            println!("Failed to remove payload file {:?}: {}", path, e);
+        }
+    }
+}
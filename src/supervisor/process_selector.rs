@@ -0,0 +1,136 @@
+use super::supervised_process::SupervisedProcess;
+use serde::{Deserialize, Serialize};
+
+///selects supervised processes for bulk operations, all given criteria have to match.
+///At least one criterion is required, `{"all": true}` selects every process
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProcessSelector {
+    #[serde(default)]
+    pub all: bool,
+    #[serde(default)]
+    pub source_id: Option<u32>,
+    ///"regular" or "sandbox"
+    #[serde(default)]
+    pub mode: Option<String>,
+    ///processes running at least this long
+    #[serde(default)]
+    pub min_age_secs: Option<u64>,
+    ///ids which are not supervised or already finished get an error result
+    #[serde(default)]
+    pub ids: Option<Vec<String>>,
+}
+
+impl ProcessSelector {
+    pub fn parse(body: &str) -> Result<Self, String> {
+        let selector: ProcessSelector =
+            serde_json::from_str(body).map_err(|e| format!("Invalid selector: {}", e))?;
+        if !selector.all
+            && selector.source_id.is_none()
+            && selector.mode.is_none()
+            && selector.min_age_secs.is_none()
+            && selector.ids.is_none()
+        {
+            return Err("Empty selector. Use {\"all\": true} to select every process".to_owned());
+        }
+        if let Some(mode) = &selector.mode {
+            if mode != "regular" && mode != "sandbox" {
+                return Err(format!(
+                    "Invalid selector: unknown mode {}, expected regular or sandbox",
+                    mode
+                ));
+            }
+        }
+        Ok(selector)
+    }
+
+    pub fn matches(&self, id: &str, process: &SupervisedProcess) -> bool {
+        self.source_id
+            .is_none_or(|source_id| process.assigned_process.source_id == source_id)
+            && self
+                .mode
+                .as_ref()
+                .is_none_or(|mode| process.mode().as_str() == mode)
+            && self
+                .min_age_secs
+                .is_none_or(|secs| process.runtime().as_secs() >= secs)
+            && self
+                .ids
+                .as_ref()
+                .is_none_or(|ids| ids.iter().any(|selected| selected == id))
+    }
+}
+
+///the outcome of a bulk operation for a single process
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectedProcessResult {
+    pub id: String,
+    pub is_success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SelectedProcessResult {
+    pub fn success(id: String, exit_code: Option<i32>) -> Self {
+        Self {
+            id,
+            is_success: true,
+            exit_code,
+            error: None,
+        }
+    }
+
+    pub fn error(id: String, error: String) -> Self {
+        Self {
+            id,
+            is_success: false,
+            exit_code: None,
+            error: Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_criteria() {
+        let selector =
+            ProcessSelector::parse(r#"{"source_id": 7, "mode": "sandbox", "min_age_secs": 30}"#)
+                .unwrap();
+        assert!(!selector.all);
+        assert_eq!(selector.source_id, Some(7));
+        assert_eq!(selector.mode.as_deref(), Some("sandbox"));
+        assert_eq!(selector.min_age_secs, Some(30));
+        assert!(selector.ids.is_none());
+
+        let selector = ProcessSelector::parse(r#"{"ids": ["1", "2"]}"#).unwrap();
+        assert_eq!(selector.ids, Some(vec!["1".to_owned(), "2".to_owned()]));
+
+        assert!(ProcessSelector::parse(r#"{"all": true}"#).unwrap().all);
+    }
+
+    #[test]
+    fn rejects_an_empty_selector() {
+        for body in ["{}", r#"{"all": false}"#] {
+            let error = ProcessSelector::parse(body).unwrap_err();
+            assert!(error.starts_with("Empty selector"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_selectors() {
+        for body in [
+            "",
+            r#"{"mode": "production"}"#,
+            r#"{"source": 7}"#,
+            r#"{"source_id": "7"}"#,
+        ] {
+            let error = ProcessSelector::parse(body).unwrap_err();
+            assert!(error.starts_with("Invalid selector"), "{}", error);
+        }
+    }
+}
@@ -1,6 +1,10 @@
 pub struct LaunchResult {
     pid: Option<u32>,
     is_success: bool,
+    //the process id is supervised already
+    is_conflict: bool,
+    //the process input is refused, the launch must not be retried
+    is_rejected: bool,
     error_message: Option<String>,
 }
 
@@ -9,10 +13,30 @@ impl LaunchResult {
         Self {
             pid: None,
             is_success: false,
+            is_conflict: false,
+            is_rejected: false,
             error_message: None,
         }
     }
 
+    pub fn set_conflict(&mut self, error_message: String) {
+        self.set_error(error_message);
+        self.is_conflict = true;
+    }
+
+    pub fn is_conflict(&self) -> bool {
+        self.is_conflict
+    }
+
+    pub fn set_rejected(&mut self, error_message: String) {
+        self.set_error(error_message);
+        self.is_rejected = true;
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        self.is_rejected
+    }
+
     pub fn set_success(&mut self, pid: u32) {
         self.is_success = true;
         self.pid = Some(pid);
@@ -36,13 +60,13 @@ impl LaunchResult {
     }
 }
 
-pub struct OldKillResult {
+pub struct KillResult {
     is_success: bool,
     exit_code: Option<i32>,
     error_message: Option<String>,
 }
 
-impl OldKillResult {
+impl KillResult {
     pub fn new() -> Self {
         Self {
             is_success: false,
@@ -65,29 +89,30 @@ impl OldKillResult {
         self.is_success
     }
 
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
     pub fn error_message(&self) -> Option<&String> {
         self.error_message.as_ref()
     }
 }
 
-pub struct KillResult {
+pub struct TerminateResult {
     is_success: bool,
-    exit_code: Option<i32>,
     error_message: Option<String>,
 }
 
-impl KillResult {
+impl TerminateResult {
     pub fn new() -> Self {
         Self {
             is_success: false,
-            exit_code: None,
             error_message: None,
         }
     }
 
-    pub fn set_success(&mut self, exit_code: Option<i32>) {
+    pub fn set_success(&mut self) {
         self.is_success = true;
-        self.exit_code = exit_code;
     }
 
     pub fn set_error(&mut self, error_message: String) {
@@ -104,22 +129,44 @@ impl KillResult {
     }
 }
 
-impl Clone for KillResult {
-    fn clone(&self) -> Self {
-        let mut clone = KillResult::new();
-        clone.is_success = self.is_success;
-        clone.exit_code = self.exit_code;
-        clone.error_message = self.error_message.clone();
-        clone
+///the result of pausing or resuming a process
+pub struct PauseResult {
+    is_success: bool,
+    error_message: Option<String>,
+}
+
+impl PauseResult {
+    pub fn new() -> Self {
+        Self {
+            is_success: false,
+            error_message: None,
+        }
+    }
+
+    pub fn set_success(&mut self) {
+        self.is_success = true;
+    }
+
+    pub fn set_error(&mut self, error_message: String) {
+        self.is_success = false;
+        self.error_message = Some(error_message);
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.is_success
+    }
+
+    pub fn error_message(&self) -> Option<&String> {
+        self.error_message.as_ref()
     }
 }
 
-pub struct TerminateResult {
+pub struct SignalResult {
     is_success: bool,
     error_message: Option<String>,
 }
 
-impl TerminateResult {
+impl SignalResult {
     pub fn new() -> Self {
         Self {
             is_success: false,
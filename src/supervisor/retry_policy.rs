@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+///decides whether a failed worker run is launched again locally instead of being reported
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    ///including the first run, so 1 means no retries
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retryable_exit_codes: Vec<i32>,
+}
+
+impl RetryPolicy {
+    pub fn new(
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+        retryable_exit_codes: Vec<i32>,
+    ) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+            retryable_exit_codes,
+        }
+    }
+
+    ///only processes which exited by themselves with a retryable code are retried,
+    ///signal deaths have no exit code
+    pub fn should_retry(&self, attempt: u32, exit_code: Option<i32>) -> bool {
+        attempt < self.max_attempts
+            && exit_code.is_some_and(|code| self.retryable_exit_codes.contains(&code))
+    }
+
+    ///exponential backoff before the given attempt (2 is the first retry)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(2).min(31);
+        self.initial_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new(4, Duration::from_secs(1), Duration::from_secs(5), vec![75])
+    }
+
+    #[test]
+    fn backoff_doubles_from_the_first_retry() {
+        let policy = policy();
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff(4), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = policy();
+        assert_eq!(policy.backoff(5), Duration::from_secs(5));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn retries_only_retryable_exit_codes_within_attempts() {
+        let policy = policy();
+        assert!(policy.should_retry(1, Some(75)));
+        assert!(policy.should_retry(3, Some(75)));
+        assert!(!policy.should_retry(4, Some(75)));
+        assert!(!policy.should_retry(1, Some(1)));
+        //killed by a signal
+        assert!(!policy.should_retry(1, None));
+    }
+}
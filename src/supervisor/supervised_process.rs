@@ -0,0 +1,191 @@
+use super::cgroup::Cgroup;
+use super::lifecycle::{IllegalTransition, Lifecycle, LifecycleState};
+use super::process_events::{ProcessEvent, ProcessEventKind};
+use crate::dispatcher::{AssignedProcess, ProcessingMode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::sync::Arc;
+use tokio::process::Child;
+use tokio::sync::{watch, Notify, RwLock};
+use tokio::time::{Duration, Instant};
+
+pub type Processes = Arc<RwLock<HashMap<String, SupervisedProcess>>>;
+pub type ExitStatusSender = watch::Sender<Option<ExitStatus>>;
+pub type ExitStatusReceiver = watch::Receiver<Option<ExitStatus>>;
+
+///why the supervisor decided to stop the process, reported to the dispatcher
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationReason {
+    MemoryLimitExceeded,
+    Timeout,
+    //the supervisor is shutting down
+    Interrupted,
+    //the payload exceeds PAYLOAD_MAX_BYTES, the process is never launched
+    PayloadRejected,
+}
+
+impl TerminationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TerminationReason::MemoryLimitExceeded => "memory limit exceeded",
+            TerminationReason::Timeout => "timeout",
+            TerminationReason::Interrupted => "interrupted",
+            TerminationReason::PayloadRejected => "payload rejected",
+        }
+    }
+}
+
+///a launched worker together with the dispatcher assignment it is working on
+#[derive(Debug)]
+pub struct SupervisedProcess {
+    pub pid: u32,
+    pub assigned_process: AssignedProcess,
+    pub cgroup: Option<Cgroup>,
+    //1 for the first run, increased on every local retry
+    pub attempt: u32,
+    //published by the task awaiting the child exit, None while the child is running
+    exit_status: ExitStatusReceiver,
+    events: Vec<ProcessEvent>,
+    termination_reason: Option<TerminationReason>,
+    started_at: Instant,
+    lifecycle: Lifecycle,
+    //set while the process is paused
+    paused_at: Option<Instant>,
+    //previous pauses only
+    paused_total: Duration,
+}
+
+impl SupervisedProcess {
+    pub fn new(
+        pid: u32,
+        assigned_process: AssignedProcess,
+        exit_status: ExitStatusReceiver,
+        cgroup: Option<Cgroup>,
+        attempt: u32,
+    ) -> Self {
+        Self {
+            pid,
+            assigned_process,
+            cgroup,
+            attempt,
+            exit_status,
+            events: vec![],
+            termination_reason: None,
+            started_at: Instant::now(),
+            lifecycle: Lifecycle::new(),
+            paused_at: None,
+            paused_total: Duration::ZERO,
+        }
+    }
+
+    pub fn mode(&self) -> ProcessingMode {
+        self.assigned_process.mode
+    }
+
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        *self.exit_status.borrow()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.exit_status().is_some()
+    }
+
+    pub fn exit_status_receiver(&self) -> ExitStatusReceiver {
+        self.exit_status.clone()
+    }
+
+    ///how long the process has been running since its launch
+    pub fn runtime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    ///how long the process has been paused in total, including the current pause
+    pub fn paused_duration(&self) -> Duration {
+        self.paused_total + self.paused_at.map(|at| at.elapsed()).unwrap_or_default()
+    }
+
+    ///runtime without pauses, deadlines are checked against it
+    pub fn active_runtime(&self) -> Duration {
+        self.runtime().saturating_sub(self.paused_duration())
+    }
+
+    pub fn events(&self) -> &[ProcessEvent] {
+        &self.events
+    }
+
+    pub fn has_event(&self, kind: ProcessEventKind) -> bool {
+        self.events.iter().any(|event| event.kind() == kind)
+    }
+
+    pub fn add_event(&mut self, kind: ProcessEventKind, message: String) {
+        println!("Process {}: {}", self.assigned_process.id, message);
+        self.events.push(ProcessEvent::new(kind, message));
+    }
+
+    pub fn termination_reason(&self) -> Option<TerminationReason> {
+        self.termination_reason
+    }
+
+    pub fn set_termination_reason(&mut self, reason: TerminationReason) {
+        self.termination_reason = Some(reason);
+    }
+
+    pub fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    pub fn state(&self) -> LifecycleState {
+        self.lifecycle.state()
+    }
+
+    pub fn transition(&mut self, to: LifecycleState) -> Result<(), IllegalTransition> {
+        self.lifecycle.transition(to)?;
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_total += paused_at.elapsed();
+        }
+        if to == LifecycleState::Paused {
+            self.paused_at = Some(Instant::now());
+        }
+        println!("Process {} is {}", self.assigned_process.id, to.as_str());
+        Ok(())
+    }
+}
+
+///starts a task owning the child: it awaits the child exit, marks the process as exited,
+///publishes the exit status and wakes up whoever waits for child exits
+pub fn watch_exit(
+    id: String,
+    mut child: Child,
+    sender: ExitStatusSender,
+    processes: Processes,
+    child_exited: Arc<Notify>,
+) {
+    tokio::task::spawn(async move {
+        let status = match child.wait().await {
+            Ok(status) => status,
+            Err(e) => {
+                //should not happen, but the slot must be released anyway
+                println!(
+                    "Failed to wait for process {}: {}. Treating it as failed",
+                    id, e
+                );
+                ExitStatus::from_raw(255 << 8)
+            }
+        };
+        println!("Process {} exited: {}", id, status);
+        let mut processes_guard = processes.write().await;
+        if let Some(process) = processes_guard.get_mut(&id) {
+            if let Err(e) = process.transition(LifecycleState::Exited) {
+                println!("Process {}: {}", id, e);
+            }
+        }
+        //published under the lock, so an exited process is never seen without its exit status.
+        //The receiver could be dropped if the process was removed already
+        let _ = sender.send(Some(status));
+        drop(processes_guard);
+        child_exited.notify_one();
+    });
+}
@@ -0,0 +1,79 @@
+use super::process_id;
+use std::path::{Path, PathBuf};
+
+//set only if the working directory root is configured
+pub const ENV_WORKDIR: &str = "PS_WORKDIR";
+
+///every process gets its own scratch directory {root}/{process_id}. It is the child cwd
+///unless the worker profile sets working_dir, so workers with relative paths keep working
+#[derive(Debug, Clone)]
+pub struct WorkdirPolicy {
+    pub root: String,
+    ///failed runs keep their directory for post-mortem debugging
+    pub retain_on_failure: bool,
+}
+
+///the id is validated, so the path never points to the root itself or outside of it
+pub fn workdir_path(root: &str, id: &str) -> std::io::Result<PathBuf> {
+    Ok(Path::new(root).join(process_id::path_component(id)?))
+}
+
+///creates an empty directory, leftovers of a previous attempt are removed first.
+///The caller makes sure no supervised process uses the directory
+pub async fn create_workdir(root: &str, id: &str) -> std::io::Result<PathBuf> {
+    let path = workdir_path(root, id)?;
+    remove_workdir(root, id).await;
+    tokio::fs::create_dir_all(&path).await?;
+    Ok(path)
+}
+
+pub async fn remove_workdir(root: &str, id: &str) {
+    let path = match workdir_path(root, id) {
+        Ok(path) => path,
+        //nothing could be created for such an id
+        Err(_) => return,
+    };
+    if let Err(e) = tokio::fs::remove_dir_all(&path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            println!("Failed to remove working directory {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_ids_outside_the_root() {
+        assert_eq!(
+            workdir_path("/srv/work", "42").unwrap(),
+            Path::new("/srv/work/42")
+        );
+        for id in ["", "..", "../other", "/etc", "a/b"] {
+            assert!(workdir_path("/srv/work", id).is_err(), "{:?}", id);
+        }
+    }
+
+    #[tokio::test]
+    async fn never_removes_the_root_or_its_parent() {
+        let parent = std::env::temp_dir().join(format!("workdir_test_{}", std::process::id()));
+        let root = parent.join("root");
+        let root_str = root.to_str().unwrap();
+        std::fs::create_dir_all(&root).unwrap();
+
+        for id in ["", "..", "."] {
+            remove_workdir(root_str, id).await;
+            assert!(create_workdir(root_str, id).await.is_err());
+        }
+        assert!(root.is_dir());
+
+        let path = create_workdir(root_str, "7").await.unwrap();
+        std::fs::write(path.join("scratch"), "x").unwrap();
+        remove_workdir(root_str, "7").await;
+        assert!(!path.exists());
+        assert!(root.is_dir());
+
+        std::fs::remove_dir_all(&parent).unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+use super::cgroup::CgroupLimits;
+use super::escalation::EscalationChain;
+use crate::dispatcher::{AssignedProcess, ProcessingMode};
+use nix::sys::resource::{setrlimit, Resource};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+pub const DEFAULT_WORKER_PROGRAM: &str = "php";
+pub const DEFAULT_WORKER_ARGS: &str = "worker/worker.php";
+//sandbox processes running at once if the sandbox profile does not limit them
+pub const DEFAULT_SANDBOX_MAX_CHILDREN_COUNT: usize = 1;
+
+///describes how a worker process is started.
+///program, args, working_dir and env values may contain `{process_id}`, `{source_id}`, `{mode}`
+///and `{supervisor_id}` placeholders, they are expanded from the assigned process on launch
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkerProfile {
+    program: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    working_dir: Option<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    ///how many processes of this profile may run at once, limited only by the total slots if None
+    #[serde(default)]
+    max_children_count: Option<usize>,
+    #[serde(default)]
+    resource_limits: ResourceLimits,
+    ///applied only if the supervisor is configured with a cgroup parent
+    #[serde(default)]
+    cgroup_limits: CgroupLimits,
+    #[serde(default)]
+    memory_limits: MemoryLimits,
+    ///wall-clock deadline, the process is terminated once it runs longer
+    #[serde(default)]
+    max_runtime_secs: Option<u64>,
+    ///signals sent on termination, SIGTERM and SIGKILL after SIGTERM_TIMEOUT_SECS if None
+    #[serde(default)]
+    termination_chain: Option<EscalationChain>,
+}
+
+///rlimits applied to the worker right before exec
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ResourceLimits {
+    ///RLIMIT_AS, virtual memory size in bytes
+    #[serde(default)]
+    pub max_memory_bytes: Option<u64>,
+    ///RLIMIT_CPU, CPU time in seconds
+    #[serde(default)]
+    pub max_cpu_secs: Option<u64>,
+    ///RLIMIT_NOFILE
+    #[serde(default)]
+    pub max_open_files: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_memory_bytes.is_none()
+            && self.max_cpu_secs.is_none()
+            && self.max_open_files.is_none()
+    }
+
+    //called in the forked child, so only async-signal-safe calls are allowed here
+    fn apply(&self) -> std::io::Result<()> {
+        if let Some(limit) = self.max_memory_bytes {
+            setrlimit(Resource::RLIMIT_AS, limit, limit)?;
+        }
+        if let Some(limit) = self.max_cpu_secs {
+            setrlimit(Resource::RLIMIT_CPU, limit, limit)?;
+        }
+        if let Some(limit) = self.max_open_files {
+            setrlimit(Resource::RLIMIT_NOFILE, limit, limit)?;
+        }
+        Ok(())
+    }
+}
+
+///anonymous RSS thresholds checked by the supervisor watchdog
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MemoryLimits {
+    ///only logged and recorded as a process event
+    #[serde(default)]
+    pub soft_limit_kb: Option<u64>,
+    ///the process is terminated once it is exceeded
+    #[serde(default)]
+    pub hard_limit_kb: Option<u64>,
+}
+
+impl MemoryLimits {
+    pub fn is_empty(&self) -> bool {
+        self.soft_limit_kb.is_none() && self.hard_limit_kb.is_none()
+    }
+}
+
+///launch profiles per processing mode, so sandbox runs never share a command,
+///environment or slots with regular ones.
+///If the sandbox profile is not configured, sandbox jobs run the regular command with the regular
+///environment and limits (the worker tells them apart by PS_PROCESSING_MODE). Either way
+///at most DEFAULT_SANDBOX_MAX_CHILDREN_COUNT of them run at once unless the profile sets the limit
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkerProfiles {
+    regular: WorkerProfile,
+    ///falls back to the regular profile if not set, see with_sandbox_defaults()
+    #[serde(default)]
+    sandbox: Option<WorkerProfile>,
+}
+
+impl WorkerProfiles {
+    pub fn new(regular: WorkerProfile, sandbox: WorkerProfile) -> Self {
+        Self {
+            regular,
+            sandbox: Some(sandbox),
+        }
+    }
+
+    ///applies the sandbox fallback, so a sandbox job can't starve regular work
+    pub fn with_sandbox_defaults(mut self) -> Self {
+        let sandbox = self.sandbox.get_or_insert_with(|| {
+            println!(
+                "WARNING: the sandbox worker profile is not configured. Sandbox jobs run the regular worker command"
+            );
+            //the regular cap is meant for regular work, sandbox gets its own below
+            WorkerProfile {
+                max_children_count: None,
+                ..self.regular.clone()
+            }
+        });
+        if sandbox.max_children_count.is_none() {
+            println!(
+                "Sandbox max children count is not set. Using default {}",
+                DEFAULT_SANDBOX_MAX_CHILDREN_COUNT
+            );
+            sandbox.max_children_count = Some(DEFAULT_SANDBOX_MAX_CHILDREN_COUNT);
+        }
+        self
+    }
+
+    pub fn for_mode(&self, mode: ProcessingMode) -> &WorkerProfile {
+        match mode {
+            ProcessingMode::Regular => &self.regular,
+            ProcessingMode::Sandbox => self.sandbox.as_ref().unwrap_or(&self.regular),
+        }
+    }
+}
+
+impl WorkerProfile {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        program: String,
+        args: Vec<String>,
+        working_dir: Option<String>,
+        env: HashMap<String, String>,
+        max_children_count: Option<usize>,
+        resource_limits: ResourceLimits,
+        cgroup_limits: CgroupLimits,
+        memory_limits: MemoryLimits,
+        max_runtime_secs: Option<u64>,
+        termination_chain: Option<EscalationChain>,
+    ) -> Self {
+        Self {
+            program,
+            args,
+            working_dir,
+            env,
+            max_children_count,
+            resource_limits,
+            cgroup_limits,
+            memory_limits,
+            max_runtime_secs,
+            termination_chain,
+        }
+    }
+
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn working_dir(&self) -> Option<&str> {
+        self.working_dir.as_deref()
+    }
+
+    pub fn env(&self) -> &HashMap<String, String> {
+        &self.env
+    }
+
+    pub fn max_children_count(&self) -> Option<usize> {
+        self.max_children_count
+    }
+
+    pub fn resource_limits(&self) -> &ResourceLimits {
+        &self.resource_limits
+    }
+
+    pub fn cgroup_limits(&self) -> &CgroupLimits {
+        &self.cgroup_limits
+    }
+
+    pub fn memory_limits(&self) -> &MemoryLimits {
+        &self.memory_limits
+    }
+
+    pub fn max_runtime_secs(&self) -> Option<u64> {
+        self.max_runtime_secs
+    }
+
+    pub fn termination_chain(&self) -> Option<&EscalationChain> {
+        self.termination_chain.as_ref()
+    }
+
+    ///builds a command ready to be spawned for the given assigned process
+    pub fn command(&self, assigned_process: &AssignedProcess) -> Command {
+        let mut command = Command::new(expand_placeholders(&self.program, assigned_process));
+        command.args(
+            self.args
+                .iter()
+                .map(|arg| expand_placeholders(arg, assigned_process)),
+        );
+        if let Some(working_dir) = &self.working_dir {
+            command.current_dir(expand_placeholders(working_dir, assigned_process));
+        }
+        command.envs(
+            self.env
+                .iter()
+                .map(|(key, value)| (key, expand_placeholders(value, assigned_process))),
+        );
+        if !self.resource_limits.is_empty() {
+            let resource_limits = self.resource_limits.clone();
+            //SAFETY: the closure only calls setrlimit, which is async-signal-safe
+            unsafe {
+                command.pre_exec(move || resource_limits.apply());
+            }
+        }
+        command
+    }
+}
+
+fn expand_placeholders(value: &str, assigned_process: &AssignedProcess) -> String {
+    value
+        .replace("{process_id}", &assigned_process.id)
+        .replace("{source_id}", &assigned_process.source_id.to_string())
+        .replace("{mode}", assigned_process.mode.as_str())
+        .replace("{supervisor_id}", &assigned_process.supervisor_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assigned_process() -> AssignedProcess {
+        let mut assigned_process = AssignedProcess::manual("42".to_owned(), "sv1".to_owned());
+        assigned_process.source_id = 7;
+        assigned_process.mode = ProcessingMode::Sandbox;
+        assigned_process
+    }
+
+    #[test]
+    fn expands_every_placeholder() {
+        let value = "--id={process_id} --source={source_id} --mode={mode} --by={supervisor_id}";
+        assert_eq!(
+            expand_placeholders(value, &assigned_process()),
+            "--id=42 --source=7 --mode=sandbox --by=sv1"
+        );
+    }
+
+    #[test]
+    fn expands_repeated_placeholders_and_keeps_the_rest() {
+        assert_eq!(
+            expand_placeholders(
+                "{process_id}/{process_id}.log {unknown}",
+                &assigned_process()
+            ),
+            "42/42.log {unknown}"
+        );
+        assert_eq!(
+            expand_placeholders("worker.php", &assigned_process()),
+            "worker.php"
+        );
+    }
+
+    #[test]
+    fn sandbox_falls_back_to_a_capped_regular_profile() {
+        let profiles: WorkerProfiles =
+            serde_json::from_str(r#"{"regular": {"program": "php", "max_children_count": 10}}"#)
+                .unwrap();
+        let profiles = profiles.with_sandbox_defaults();
+        let sandbox = profiles.for_mode(ProcessingMode::Sandbox);
+        assert_eq!(sandbox.program(), "php");
+        assert_eq!(
+            sandbox.max_children_count(),
+            Some(DEFAULT_SANDBOX_MAX_CHILDREN_COUNT)
+        );
+        assert_eq!(
+            profiles
+                .for_mode(ProcessingMode::Regular)
+                .max_children_count(),
+            Some(10)
+        );
+    }
+}